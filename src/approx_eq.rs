@@ -0,0 +1,76 @@
+use approx::{AbsDiffEq, RelativeEq};
+
+use crate::{Rational, mul_wide};
+
+/// Compares the nearest `f64` to the value like `approx` compares two
+/// floats.
+impl AbsDiffEq<f64> for Rational {
+    type Epsilon = f64;
+
+    fn default_epsilon() -> f64 {
+        f64::default_epsilon()
+    }
+
+    fn abs_diff_eq(&self, other: &f64, epsilon: f64) -> bool {
+        self.to_f64().abs_diff_eq(other, epsilon)
+    }
+}
+
+impl RelativeEq<f64> for Rational {
+    fn default_max_relative() -> f64 {
+        f64::default_max_relative()
+    }
+
+    fn relative_eq(&self, other: &f64, epsilon: f64, max_relative: f64) -> bool {
+        self.to_f64().relative_eq(other, epsilon, max_relative)
+    }
+}
+
+// |self - other| as a magnitude and denominator, exact for any pair
+fn abs_diff(a: &Rational, b: &Rational) -> (u128, u128) {
+    let (num, den) = a.wide_sum(*b, -1);
+    (num.unsigned_abs(), den as u128)
+}
+
+// whether a/b <= c/d, where each cross product fits in 256 bits
+fn le(a: u128, b: u128, c: u128, d: u128) -> bool {
+    mul_wide(a, d) <= mul_wide(c, b)
+}
+
+fn parts(v: &Rational) -> (u128, u128) {
+    (v.numer().unsigned_abs() as u128, v.denom() as u128)
+}
+
+/// Compares exactly with a rational tolerance, which defaults to zero.
+impl AbsDiffEq for Rational {
+    type Epsilon = Rational;
+
+    fn default_epsilon() -> Rational {
+        Rational::ZERO
+    }
+
+    fn abs_diff_eq(&self, other: &Rational, epsilon: Rational) -> bool {
+        let (num, den) = abs_diff(self, other);
+        let (eps, eps_den) = parts(&epsilon);
+        !epsilon.is_negative() && le(num, den, eps, eps_den)
+    }
+}
+
+/// Also accepts a difference of up to `max_relative` times the larger
+/// magnitude, computed exactly like the absolute tolerance.
+impl RelativeEq for Rational {
+    fn default_max_relative() -> Rational {
+        Rational::ZERO
+    }
+
+    fn relative_eq(&self, other: &Rational, epsilon: Rational, max_relative: Rational) -> bool {
+        if self.abs_diff_eq(other, epsilon) {
+            return true;
+        }
+        let (a, b) = (parts(self), parts(other));
+        let largest = if le(a.0, a.1, b.0, b.1) { b } else { a };
+        let (rel, rel_den) = parts(&max_relative);
+        let (num, den) = abs_diff(self, other);
+        !max_relative.is_negative() && le(num, den, largest.0 * rel, largest.1 * rel_den)
+    }
+}
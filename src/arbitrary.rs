@@ -0,0 +1,29 @@
+use proptest::prelude::*;
+
+use crate::Rational;
+
+/// Reduced values of every sign and size: mostly small fractions, which
+/// shrink towards zero, mixed with parts spread over the whole i64 range
+/// and the extremes `i64::MIN`, `i64::MAX` and `1/i64::MAX`.
+impl Arbitrary for Rational {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Rational>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        let extremes = [
+            Rational::from(i64::MIN),
+            Rational::from(i64::MAX),
+            Rational::new_unchecked(1, i64::MAX),
+            Rational::new_unchecked(-1, i64::MAX),
+            Rational::new_unchecked(i64::MAX - 1, i64::MAX),
+        ];
+        prop_oneof![
+            6 => (-1000i64..=1000, 1i64..=1000),
+            3 => (any::<i64>(), 1..=i64::MAX),
+            1 => proptest::sample::select(extremes.to_vec()).prop_map(|v| v.into_parts()),
+        ]
+        // a positive denominator never fails
+        .prop_map(|(num, den)| Rational::new(num, den).unwrap())
+        .boxed()
+    }
+}
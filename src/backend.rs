@@ -0,0 +1,318 @@
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display};
+use std::hash::Hash;
+use std::num::{NonZeroI32, NonZeroI64, NonZeroI128};
+use std::ops::{Div, Neg, Rem};
+
+use crate::{Error, Ratio, Rational, Rational32, Rational128, Result, gcd128};
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// An integer type that can back a [`Ratio`]: `i32`, `i64` (the default)
+/// or `i128`.
+///
+/// i32 and i64 are computed exactly on wider intermediates. i128 cancels
+/// common factors before multiplying instead, so a sum can fail with
+/// [`Error::Overflow`] when its unreduced numerator exceeds i128 although the
+/// result would fit.
+///
+/// The trait is sealed. Its items are the per-backend pieces of the
+/// arithmetic and not meant to be used directly.
+pub trait Integer:
+    Copy
+    + Ord
+    + Hash
+    + Debug
+    + Display
+    + Div<Output = Self>
+    + Rem<Output = Self>
+    + Neg<Output = Self>
+    + private::Sealed
+{
+    #[doc(hidden)]
+    type NonZero: Copy + Eq + Hash;
+    #[doc(hidden)]
+    const ZERO: Self;
+    #[doc(hidden)]
+    const ONE: Self;
+    #[doc(hidden)]
+    const NZ_ONE: Self::NonZero;
+    #[doc(hidden)]
+    fn get(den: Self::NonZero) -> Self;
+    #[doc(hidden)]
+    fn checked_neg(self) -> Option<Self>;
+    #[doc(hidden)]
+    fn unsigned_abs(self) -> u128;
+    /// The canonical form of `num/den` for a nonzero `den`, if it fits.
+    #[doc(hidden)]
+    fn canonical(num: Self, den: Self) -> Option<Ratio<Self>>;
+    #[doc(hidden)]
+    fn add_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>>;
+    #[doc(hidden)]
+    fn sub_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>>;
+    #[doc(hidden)]
+    fn mul_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>>;
+    /// `a / b` for a nonzero `b`.
+    #[doc(hidden)]
+    fn div_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>>;
+    #[doc(hidden)]
+    fn cmp_rational(a: &Ratio<Self>, b: &Ratio<Self>) -> Ordering;
+}
+
+// i64 computes on i128 intermediates, so a result is only refused when it
+// does not fit itself
+impl private::Sealed for i64 {}
+
+impl Integer for i64 {
+    type NonZero = NonZeroI64;
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+    const NZ_ONE: NonZeroI64 = NonZeroI64::new(1).unwrap();
+
+    fn get(den: NonZeroI64) -> Self {
+        den.get()
+    }
+
+    fn checked_neg(self) -> Option<Self> {
+        self.checked_neg()
+    }
+
+    fn unsigned_abs(self) -> u128 {
+        self.unsigned_abs() as u128
+    }
+
+    fn canonical(num: Self, den: Self) -> Option<Rational> {
+        Rational::from_i128(num as i128, den as i128)
+    }
+
+    fn add_rational(a: Rational, b: Rational) -> Option<Rational> {
+        let (num, den) = a.wide_sum(b, 1);
+        Rational::from_i128(num, den)
+    }
+
+    fn sub_rational(a: Rational, b: Rational) -> Option<Rational> {
+        let (num, den) = a.wide_sum(b, -1);
+        Rational::from_i128(num, den)
+    }
+
+    fn mul_rational(a: Rational, b: Rational) -> Option<Rational> {
+        let (num, den) = a.wide_mul(b);
+        Rational::from_i128(num, den)
+    }
+
+    fn div_rational(a: Rational, b: Rational) -> Option<Rational> {
+        let num = a.0 as i128 * b.1.get() as i128;
+        let den = a.1.get() as i128 * b.0 as i128;
+        Rational::from_i128(num, den)
+    }
+
+    // values of different sign or with the same denominator are told apart
+    // without multiplying; otherwise the cross products are formed in i128,
+    // where they always fit
+    fn cmp_rational(a: &Rational, b: &Rational) -> Ordering {
+        let sign = a.0.signum().cmp(&b.0.signum());
+        if sign.is_ne() || a.0 == 0 {
+            return sign;
+        }
+        if a.1 == b.1 {
+            return a.0.cmp(&b.0);
+        }
+        (a.0 as i128 * b.1.get() as i128).cmp(&(b.0 as i128 * a.1.get() as i128))
+    }
+}
+
+// i32 is computed exactly by the i64 backend and narrowed
+impl private::Sealed for i32 {}
+
+impl Integer for i32 {
+    type NonZero = NonZeroI32;
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+    const NZ_ONE: NonZeroI32 = NonZeroI32::new(1).unwrap();
+
+    fn get(den: NonZeroI32) -> Self {
+        den.get()
+    }
+
+    fn checked_neg(self) -> Option<Self> {
+        self.checked_neg()
+    }
+
+    fn unsigned_abs(self) -> u128 {
+        self.unsigned_abs() as u128
+    }
+
+    fn canonical(num: Self, den: Self) -> Option<Ratio<Self>> {
+        narrow(i64::canonical(num.into(), den.into())?)
+    }
+
+    fn add_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>> {
+        narrow(i64::add_rational(a.into(), b.into())?)
+    }
+
+    fn sub_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>> {
+        narrow(i64::sub_rational(a.into(), b.into())?)
+    }
+
+    fn mul_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>> {
+        narrow(i64::mul_rational(a.into(), b.into())?)
+    }
+
+    fn div_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>> {
+        narrow(i64::div_rational(a.into(), b.into())?)
+    }
+
+    fn cmp_rational(a: &Ratio<Self>, b: &Ratio<Self>) -> Ordering {
+        i64::cmp_rational(&(*a).into(), &(*b).into())
+    }
+}
+
+fn narrow(v: Rational) -> Option<Rational32> {
+    Some(Ratio(v.0.try_into().ok()?, v.1.try_into().ok()?))
+}
+
+// i128 has no wider type, so common factors are cancelled before
+// multiplying to keep intermediates as small as the result allows. Unlike
+// the other backends it can still report an overflow for a sum whose
+// intermediate numerator exceeds i128 although the reduced result fits.
+impl private::Sealed for i128 {}
+
+impl Integer for i128 {
+    type NonZero = NonZeroI128;
+    const ZERO: Self = 0;
+    const ONE: Self = 1;
+    const NZ_ONE: NonZeroI128 = NonZeroI128::new(1).unwrap();
+
+    fn get(den: NonZeroI128) -> Self {
+        den.get()
+    }
+
+    fn checked_neg(self) -> Option<Self> {
+        self.checked_neg()
+    }
+
+    fn unsigned_abs(self) -> u128 {
+        self.unsigned_abs()
+    }
+
+    fn canonical(num: Self, den: Self) -> Option<Ratio<Self>> {
+        let (n, d) = (num.unsigned_abs(), den.unsigned_abs());
+        let g = gcd128(n, d);
+        signed((num < 0) != (den < 0), n / g, d / g)
+    }
+
+    fn add_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>> {
+        sum(a, b, i128::checked_add)
+    }
+
+    fn sub_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>> {
+        sum(a, b, i128::checked_sub)
+    }
+
+    fn mul_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>> {
+        let (an, ad) = (a.0.unsigned_abs(), a.1.get() as u128);
+        let (bn, bd) = (b.0.unsigned_abs(), b.1.get() as u128);
+        if an == 0 || bn == 0 {
+            return Some(Ratio::default());
+        }
+        let (g1, g2) = (gcd128(an, bd), gcd128(bn, ad));
+        let num = (an / g1).checked_mul(bn / g2)?;
+        let den = (ad / g2).checked_mul(bd / g1)?;
+        signed((a.0 < 0) != (b.0 < 0), num, den)
+    }
+
+    fn div_rational(a: Ratio<Self>, b: Ratio<Self>) -> Option<Ratio<Self>> {
+        let (an, ad) = (a.0.unsigned_abs(), a.1.get() as u128);
+        let (bn, bd) = (b.0.unsigned_abs(), b.1.get() as u128);
+        if an == 0 {
+            return Some(Ratio::default());
+        }
+        let (g1, g2) = (gcd128(an, bn), gcd128(ad, bd));
+        let num = (an / g1).checked_mul(bd / g2)?;
+        let den = (ad / g2).checked_mul(bn / g1)?;
+        signed((a.0 < 0) != (b.0 < 0), num, den)
+    }
+
+    // compares the integer parts and then the reciprocals of the fractional
+    // parts, the continued fraction expansions, which never overflows
+    fn cmp_rational(a: &Ratio<Self>, b: &Ratio<Self>) -> Ordering {
+        let (mut n1, mut d1) = (a.0, a.1.get());
+        let (mut n2, mut d2) = (b.0, b.1.get());
+        loop {
+            let (q1, q2) = (n1.div_euclid(d1), n2.div_euclid(d2));
+            if q1 != q2 {
+                return q1.cmp(&q2);
+            }
+            let (r1, r2) = (n1.rem_euclid(d1), n2.rem_euclid(d2));
+            match (r1 == 0, r2 == 0) {
+                (true, true) => return Ordering::Equal,
+                (true, false) => return Ordering::Less,
+                (false, true) => return Ordering::Greater,
+                // r1/d1 < r2/d2 exactly when d2/r2 < d1/r1
+                (false, false) => (n1, d1, n2, d2) = (d2, r2, d1, r1),
+            }
+        }
+    }
+}
+
+// `a ± b` over the least common denominator, see Knuth's Seminumerical
+// Algorithms 4.5.1
+fn sum(a: Rational128, b: Rational128, op: fn(i128, i128) -> Option<i128>) -> Option<Rational128> {
+    let (ad, bd) = (a.1.get(), b.1.get());
+    let g = gcd128(ad as u128, bd as u128) as i128;
+    let t = op(a.0.checked_mul(bd / g)?, b.0.checked_mul(ad / g)?)?;
+    if t == 0 {
+        return Some(Ratio::default());
+    }
+    let g2 = gcd128(t.unsigned_abs(), g as u128) as i128;
+    let den = (ad / g).checked_mul(bd / g2)?;
+    Some(Ratio(t / g2, NonZeroI128::new(den).unwrap()))
+}
+
+// `±num/den` for coprime magnitudes, if it fits
+fn signed(negative: bool, num: u128, den: u128) -> Option<Rational128> {
+    let num = if negative {
+        0i128.checked_sub_unsigned(num)?
+    } else {
+        i128::try_from(num).ok()?
+    };
+    Some(Ratio(num, NonZeroI128::new(i128::try_from(den).ok()?)?))
+}
+
+impl From<Rational32> for Rational {
+    fn from(v: Rational32) -> Self {
+        Ratio(v.0.into(), v.1.into())
+    }
+}
+
+impl From<Rational32> for Rational128 {
+    fn from(v: Rational32) -> Self {
+        Ratio(v.0.into(), v.1.into())
+    }
+}
+
+impl From<Rational> for Rational128 {
+    fn from(v: Rational) -> Self {
+        Ratio(v.0.into(), v.1.into())
+    }
+}
+
+/// Fails with [`Error::Overflow`] if either part does not fit.
+impl TryFrom<Rational> for Rational32 {
+    type Error = Error;
+
+    fn try_from(v: Rational) -> Result<Self> {
+        narrow(v).ok_or(Error::Overflow { span: None })
+    }
+}
+
+/// Fails with [`Error::Overflow`] if either part does not fit.
+impl TryFrom<Rational128> for Rational {
+    type Error = Error;
+
+    fn try_from(v: Rational128) -> Result<Self> {
+        Rational::from_i128(v.0, v.1.get()).ok_or(Error::Overflow { span: None })
+    }
+}
@@ -0,0 +1,275 @@
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
+
+use num_bigint::{BigInt, Sign};
+use num_integer::Integer as _;
+
+use crate::{Context, Error, Number, Rational, Result, Trace, eval};
+
+/// An exact fraction of any size, for computations like
+/// `1/3 + 1/7 + 1/11 + ...` whose denominators outgrow every fixed width.
+///
+/// It keeps the canonical form of [`Rational`] and offers the same operators,
+/// which never overflow. Expressions are evaluated with
+/// [`BigRational::run_expr`]; their functions still compute on `Rational`
+/// and fail with [`Error::Overflow`] for arguments that do not fit.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BigRational {
+    num: BigInt,
+    // always positive
+    den: BigInt,
+}
+
+impl BigRational {
+    // canonical form of num/den, den being nonzero
+    fn reduce(num: BigInt, den: BigInt) -> Self {
+        let g = num.gcd(&den);
+        let (num, den) = (num / &g, den / g);
+        if den.sign() == Sign::Minus {
+            Self {
+                num: -num,
+                den: -den,
+            }
+        } else {
+            Self { num, den }
+        }
+    }
+
+    /// The canonical form of `num/den`, failing with
+    /// [`Error::DivisionByZero`] if `den` is zero.
+    pub fn new(num: impl Into<BigInt>, den: impl Into<BigInt>) -> Result<Self> {
+        let den = den.into();
+        if den.sign() == Sign::NoSign {
+            return Err(Error::DivisionByZero);
+        }
+        Ok(Self::reduce(num.into(), den))
+    }
+
+    /// The numerator, which carries the sign.
+    pub fn numer(&self) -> &BigInt {
+        &self.num
+    }
+
+    /// The denominator, always positive.
+    pub fn denom(&self) -> &BigInt {
+        &self.den
+    }
+
+    /// The numerator and denominator of the canonical form.
+    pub fn into_parts(self) -> (BigInt, BigInt) {
+        (self.num, self.den)
+    }
+
+    /// Evaluates `expr` like [`Rational::run_expr`], without ever
+    /// overflowing.
+    pub fn run_expr(expr: &str) -> Result<Self> {
+        Self::run_expr_with_context(expr, &Context::default())
+    }
+
+    /// Evaluates `expr` like [`Rational::run_expr_with_context`], without
+    /// ever overflowing.
+    pub fn run_expr_with_context(expr: &str, ctx: &Context) -> Result<Self> {
+        eval(expr, 0, ctx, &mut Trace::default())
+    }
+
+    /// The quotient, failing with [`Error::DivisionByZero`].
+    pub fn checked_div(&self, other: &Self) -> Result<Self> {
+        if other.num.sign() == Sign::NoSign {
+            return Err(Error::DivisionByZero);
+        }
+        Ok(Self::reduce(&self.num * &other.den, &self.den * &other.num))
+    }
+
+    /// Formats the reduced numerator and denominator in `radix`, see
+    /// [`Rational::to_radix_fraction_string`].
+    ///
+    /// Panics if `radix` is not in `2..=36`.
+    pub fn to_radix_fraction_string(&self, radix: u32) -> String {
+        assert!((2..=36).contains(&radix), "radix must be in 2..=36");
+        let num = self.num.to_str_radix(radix);
+        if self.den == BigInt::from(1) {
+            num
+        } else {
+            format!("{num}/{}", self.den.to_str_radix(radix))
+        }
+    }
+}
+
+impl Number for BigRational {
+    fn from_rational(v: Rational) -> Self {
+        v.into()
+    }
+
+    fn to_rational(&self) -> Result<Rational> {
+        self.try_into()
+    }
+
+    fn try_add(&self, rhs: &Self) -> Result<Self> {
+        Ok(self.clone() + rhs.clone())
+    }
+
+    fn try_sub(&self, rhs: &Self) -> Result<Self> {
+        Ok(self.clone() - rhs.clone())
+    }
+
+    fn try_mul(&self, rhs: &Self) -> Result<Self> {
+        Ok(self.clone() * rhs.clone())
+    }
+
+    fn try_div(&self, rhs: &Self) -> Result<Self> {
+        self.checked_div(rhs)
+    }
+
+    fn from_literal(int: &[u8], frac: &[u8], period: &[u8]) -> Result<Self> {
+        let number = |digits: &[u8]| digits.iter().fold(BigInt::from(0), |n, &d| n * 10 + d);
+        let scale = BigInt::from(10).pow(frac.len() as u32);
+        let value = Self::reduce(number(&[int, frac].concat()), scale.clone());
+        if period.is_empty() {
+            return Ok(value);
+        }
+        // the period repeating after FRAC adds PERIOD / (scale * (10^len - 1))
+        let nines = BigInt::from(10).pow(period.len() as u32) - 1;
+        Ok(value + Self::reduce(number(period), scale * nines))
+    }
+}
+
+impl From<Rational> for BigRational {
+    fn from(v: Rational) -> Self {
+        let (num, den) = v.into_parts();
+        Self {
+            num: num.into(),
+            den: den.into(),
+        }
+    }
+}
+
+impl From<i64> for BigRational {
+    fn from(v: i64) -> Self {
+        Self {
+            num: v.into(),
+            den: 1.into(),
+        }
+    }
+}
+
+/// Fails with [`Error::Overflow`] if either part does not fit in i64.
+impl TryFrom<&BigRational> for Rational {
+    type Error = Error;
+
+    fn try_from(v: &BigRational) -> Result<Self> {
+        let overflow = |_| Error::Overflow { span: None };
+        let num = i64::try_from(&v.num).map_err(overflow)?;
+        let den = i64::try_from(&v.den).map_err(overflow)?;
+        Rational::new(num, den)
+    }
+}
+
+impl Default for BigRational {
+    fn default() -> Self {
+        0.into()
+    }
+}
+
+impl PartialOrd for BigRational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigRational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.num * &other.den).cmp(&(&other.num * &self.den))
+    }
+}
+
+impl Neg for BigRational {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self {
+            num: -self.num,
+            den: self.den,
+        }
+    }
+}
+
+impl Add for BigRational {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::reduce(
+            self.num * &rhs.den + rhs.num * &self.den,
+            self.den * rhs.den,
+        )
+    }
+}
+
+impl AddAssign for BigRational {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = std::mem::take(self) + rhs;
+    }
+}
+
+impl Sub for BigRational {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::reduce(
+            self.num * &rhs.den - rhs.num * &self.den,
+            self.den * rhs.den,
+        )
+    }
+}
+
+impl Mul for BigRational {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Self::reduce(self.num * rhs.num, self.den * rhs.den)
+    }
+}
+
+impl Div for BigRational {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        self.checked_div(&rhs).expect("cannot divide by zero")
+    }
+}
+
+impl Sum for BigRational {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Self::add)
+    }
+}
+
+impl Product for BigRational {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(1.into(), Self::mul)
+    }
+}
+
+impl Debug for BigRational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.num, self.den)
+    }
+}
+
+/// Writes values like [`Rational`] does, e.g. `3+1/2` or `-3-1/2`.
+impl std::fmt::Display for BigRational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let (int, rem) = (&self.num / &self.den, &self.num % &self.den);
+        if self.den == BigInt::from(1) {
+            write!(f, "{}", self.num)
+        } else if int.sign() == Sign::NoSign {
+            write!(f, "{rem}/{}", self.den)
+        } else if rem.sign() == Sign::Minus {
+            // -(3+1/2) as `-3-1/2` rather than `-3+-1/2`
+            write!(f, "{int}-{}/{}", -rem, self.den)
+        } else {
+            write!(f, "{int}+{rem}/{}", self.den)
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::{Rational, Result};
+
+type ContextFunction = Arc<dyn Fn(Rational) -> Result<Rational> + Send + Sync>;
+
+/// Named values and functions that expressions evaluated with
+/// [`Rational::run_expr_with_context`] can refer to. Names are an ASCII
+/// letter followed by letters and digits, like function names, or are written
+/// as `$NAME` which also allows digits and underscores anywhere.
+#[derive(Clone, Default)]
+pub struct Context {
+    vars: HashMap<String, Rational>,
+    functions: HashMap<String, ContextFunction>,
+}
+
+impl Context {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Binds `name` to `value`, returning the value it had before.
+    pub fn set(&mut self, name: impl Into<String>, value: Rational) -> Option<Rational> {
+        self.vars.insert(name.into(), value)
+    }
+
+    pub fn get(&self, name: &str) -> Option<Rational> {
+        self.vars.get(name).copied()
+    }
+
+    /// Makes `f` callable as `name(...)`. The builtin functions take
+    /// precedence over ones with the same name.
+    pub fn set_function(
+        &mut self,
+        name: impl Into<String>,
+        f: impl Fn(Rational) -> Result<Rational> + Send + Sync + 'static,
+    ) {
+        self.functions.insert(name.into(), Arc::new(f));
+    }
+
+    pub(crate) fn function(&self, name: &str) -> Option<&ContextFunction> {
+        self.functions.get(name)
+    }
+}
+
+impl std::fmt::Debug for Context {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Context")
+            .field("vars", &self.vars)
+            .field("functions", &self.functions.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
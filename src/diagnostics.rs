@@ -0,0 +1,27 @@
+use crate::Rational;
+
+/// The largest parts of the values an expression evaluated with
+/// [`Rational::run_expr_with_diagnostics`]
+/// went through, to tell how close it came to overflowing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Diagnostics {
+    pub max_numerator: u64,
+    pub max_denominator: u64,
+}
+
+impl Diagnostics {
+    pub(crate) fn record(&mut self, v: Rational) {
+        self.max_numerator = self.max_numerator.max(v.0.unsigned_abs());
+        self.max_denominator = self.max_denominator.max(v.1.get() as u64);
+    }
+
+    /// Whether a numerator or denominator came within `factor` of overflowing,
+    /// i.e. exceeded `i64::MAX / factor`. A factor of 1 or 0 never warns.
+    pub fn near_overflow(&self, factor: u64) -> bool {
+        if factor <= 1 {
+            return false;
+        }
+        let limit = i64::MAX as u64 / factor;
+        self.max_numerator > limit || self.max_denominator > limit
+    }
+}
@@ -0,0 +1,231 @@
+use std::fmt::{Display, Formatter, Result};
+
+use crate::{Integer, Ratio, RoundingMode, positional_string, radix_digits};
+
+/// Writes a [`Ratio`] as an improper fraction like `7/2` or `-7/2`, see
+/// [`Ratio::display_improper`].
+#[derive(Clone, Copy)]
+pub struct Improper<T: Integer = i64>(pub(crate) Ratio<T>);
+
+/// Writes a [`Ratio`] as a mixed number like `3 1/2` or `-3 1/2`, see
+/// [`Ratio::display_mixed`].
+#[derive(Clone, Copy)]
+pub struct Mixed<T: Integer = i64>(pub(crate) Ratio<T>);
+
+impl<T: Integer> Display for Improper<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let (negative, num, den) = self.0.sign_magnitude();
+        let s = match den {
+            1 => num.to_string(),
+            _ => format!("{num}/{den}"),
+        };
+        f.pad_integral(!negative, "", &s)
+    }
+}
+
+impl<T: Integer> Display for Mixed<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write_mixed(&self.0, ' ', f)
+    }
+}
+
+// writes `v` as `{int}{sep}{rem}/{den}`, leaving out a zero `int` or `rem`,
+// and pads it like an integer
+pub(crate) fn write_mixed<T: Integer>(v: &Ratio<T>, sep: char, f: &mut Formatter<'_>) -> Result {
+    let (negative, num, den) = v.sign_magnitude();
+    let (int, rem) = (num / den, num % den);
+    let s = if rem == 0 {
+        int.to_string()
+    } else if int == 0 {
+        format!("{rem}/{den}")
+    } else {
+        format!("{int}{sep}{rem}/{den}")
+    };
+    f.pad_integral(!negative, "", &s)
+}
+
+/// How [`Ratio::display_latex`] writes a value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatexOptions {
+    /// Write values beyond ±1 as a mixed number like `3\frac{1}{2}` rather
+    /// than `\frac{7}{2}`.
+    pub mixed: bool,
+    /// Use the text-style `\tfrac` rather than `\frac`.
+    pub tfrac: bool,
+}
+
+/// Writes a [`Ratio`] as LaTeX math, see [`Ratio::display_latex`].
+#[derive(Clone, Copy)]
+pub struct Latex<T: Integer = i64>(pub(crate) Ratio<T>, pub(crate) LatexOptions);
+
+impl<T: Integer> Display for Latex<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let (negative, num, den) = self.0.sign_magnitude();
+        let command = if self.1.tfrac { "tfrac" } else { "frac" };
+        let (int, rem) = (num / den, num % den);
+        let s = if rem == 0 {
+            int.to_string()
+        } else if self.1.mixed && int != 0 {
+            format!("{int}\\{command}{{{rem}}}{{{den}}}")
+        } else {
+            format!("\\{command}{{{num}}}{{{den}}}")
+        };
+        f.pad_integral(!negative, "", &s)
+    }
+}
+
+/// Writes a [`Ratio`] scaled to percent or per-mille with a fixed number
+/// of digits, see [`Ratio::display_percent`].
+#[derive(Clone, Copy)]
+pub struct Scaled<T: Integer = i64> {
+    pub(crate) value: Ratio<T>,
+    // the power of ten to scale by
+    pub(crate) exp: usize,
+    pub(crate) suffix: &'static str,
+    pub(crate) digits: usize,
+    pub(crate) mode: RoundingMode,
+}
+
+impl<T: Integer> Scaled<T> {
+    /// Rounds the last digit using `mode` rather than half to even.
+    pub fn rounding(self, mode: RoundingMode) -> Self {
+        Self { mode, ..self }
+    }
+}
+
+impl<T: Integer> Display for Scaled<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let (negative, num, den) = self.value.sign_magnitude();
+        // scaling by 10^exp only moves the point of the unscaled expansion,
+        // which therefore never overflows
+        let s = positional_string(negative, num, den, 10, self.digits + self.exp, self.mode);
+        let (negative, s) = match s.strip_prefix('-') {
+            Some(magnitude) => (true, magnitude),
+            None => (false, s.as_str()),
+        };
+        let (int, frac) = s.split_once('.').unwrap_or((s, ""));
+        let (shifted, frac) = frac.split_at(self.exp);
+        let mut out = format!("{int}{shifted}")
+            .trim_start_matches('0')
+            .to_string();
+        if out.is_empty() {
+            out.push('0');
+        }
+        if !frac.is_empty() {
+            out.push('.');
+            out.push_str(frac);
+        }
+        out.push_str(self.suffix);
+        f.pad_integral(!negative, "", &out)
+    }
+}
+
+/// Writes a [`Ratio`] with Unicode fraction glyphs, see
+/// [`Ratio::display_unicode`].
+#[derive(Clone, Copy)]
+pub struct Unicode<T: Integer = i64>(pub(crate) Ratio<T>);
+
+// the precomposed vulgar fractions, by numerator and denominator
+const VULGAR: [(u128, u128, char); 18] = [
+    (1, 2, '½'),
+    (1, 3, '⅓'),
+    (2, 3, '⅔'),
+    (1, 4, '¼'),
+    (3, 4, '¾'),
+    (1, 5, '⅕'),
+    (2, 5, '⅖'),
+    (3, 5, '⅗'),
+    (4, 5, '⅘'),
+    (1, 6, '⅙'),
+    (5, 6, '⅚'),
+    (1, 7, '⅐'),
+    (1, 8, '⅛'),
+    (3, 8, '⅜'),
+    (5, 8, '⅝'),
+    (7, 8, '⅞'),
+    (1, 9, '⅑'),
+    (1, 10, '⅒'),
+];
+
+const SUPERSCRIPT: [char; 10] = ['⁰', '¹', '²', '³', '⁴', '⁵', '⁶', '⁷', '⁸', '⁹'];
+const SUBSCRIPT: [char; 10] = ['₀', '₁', '₂', '₃', '₄', '₅', '₆', '₇', '₈', '₉'];
+
+impl<T: Integer> Display for Unicode<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let (negative, num, den) = self.0.sign_magnitude();
+        let (int, rem) = (num / den, num % den);
+        let mut s = match int {
+            0 if rem != 0 => String::new(),
+            _ => int.to_string(),
+        };
+        if rem != 0 {
+            match VULGAR.iter().find(|&&(n, d, _)| (n, d) == (rem, den)) {
+                Some(&(_, _, glyph)) => s.push(glyph),
+                None => {
+                    let digits = |v: u128, glyphs: &[char; 10]| {
+                        v.to_string()
+                            .bytes()
+                            .map(|b| glyphs[(b - b'0') as usize])
+                            .collect::<String>()
+                    };
+                    s.push_str(&digits(rem, &SUPERSCRIPT));
+                    s.push('⁄');
+                    s.push_str(&digits(den, &SUBSCRIPT));
+                }
+            }
+        }
+        f.pad_integral(!negative, "", &s)
+    }
+}
+
+// writes `v` in `radix` like `1a/ff`, or with a precision as the expansion
+// in `radix` like `0.1a`, padded like an integer and with `prefix` in front
+// of each part in the alternate form
+fn write_radix<T: Integer>(
+    v: &Ratio<T>,
+    radix: u32,
+    prefix: &str,
+    upper: bool,
+    f: &mut Formatter<'_>,
+) -> Result {
+    let (negative, num, den) = v.sign_magnitude();
+    let case = |s: String| if upper { s.to_uppercase() } else { s };
+    let prefix = if f.alternate() { prefix } else { "" };
+    let (negative, s) = match f.precision() {
+        Some(digits) => {
+            let s = positional_string(negative, num, den, radix, digits, RoundingMode::HalfEven);
+            // the sign is dropped if the value rounds to zero
+            match s.strip_prefix('-') {
+                Some(magnitude) => (true, case(magnitude.to_string())),
+                None => (false, case(s)),
+            }
+        }
+        None => {
+            let num = case(radix_digits(num, radix));
+            match den {
+                1 => (negative, num),
+                _ => (
+                    negative,
+                    format!("{num}/{prefix}{}", case(radix_digits(den, radix))),
+                ),
+            }
+        }
+    };
+    f.pad_integral(!negative, "", &format!("{prefix}{s}"))
+}
+
+macro_rules! radix_fmt_impl {
+    ($($Trait:ident $radix:literal $prefix:literal $upper:literal),*) => {$(
+        /// Writes the numerator and denominator in the trait's radix, e.g.
+        /// `{:#x}` writes `26/255` as `0x1a/0xff`. A precision writes the
+        /// expansion in that radix instead, rounded half to even, e.g.
+        /// `{:.2x}` writes `1/3` as `0.55`.
+        impl<T: Integer> std::fmt::$Trait for Ratio<T> {
+            fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+                write_radix(self, $radix, $prefix, $upper, f)
+            }
+        }
+    )*};
+}
+
+radix_fmt_impl![Binary 2 "0b" false, Octal 8 "0o" false, LowerHex 16 "0x" false, UpperHex 16 "0x" true];
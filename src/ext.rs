@@ -0,0 +1,33 @@
+use crate::{OVERFLOW, Rational, Rational128};
+
+/// Constructors on plain integers, so `3.over(4)` reads like the fraction it
+/// builds.
+pub trait RatioExt {
+    /// `self / den`. Panics if `den` is zero or the reduced fraction does
+    /// not fit.
+    fn over(self, den: Self) -> Rational;
+
+    /// `self / 100`, e.g. `25.per_cent()` is `1/4`.
+    fn per_cent(self) -> Rational;
+}
+
+macro_rules! ratio_ext_impl {
+    [$($i:ident),*] => {
+        $(
+            impl RatioExt for $i {
+                fn over(self, den: Self) -> Rational {
+                    // reduced in i128 first, so e.g. `u64::MAX.over(u64::MAX)`
+                    // is 1
+                    let v = Rational128::new(self.into(), den.into()).expect("cannot divide by zero");
+                    Rational::try_from(v).expect(OVERFLOW)
+                }
+
+                fn per_cent(self) -> Rational {
+                    self.over(100)
+                }
+            }
+        )*
+    };
+}
+
+ratio_ext_impl![i32, u32, i64, u64];
@@ -0,0 +1,206 @@
+use std::cmp::Ordering;
+use std::fmt::Debug;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::Rational;
+
+/// A [`Rational`] extended with the infinities `1/0` and `-1/0` and the
+/// undefined `0/0`, for interval and geometry work where dividing by zero
+/// should not be an error.
+///
+/// Finite values behave exactly like `Rational`, including panicking on
+/// overflow. A nonzero value divided by zero is the infinity of its sign,
+/// and the forms without a value, like `0/0`, `0 * inf` or `inf - inf`, are
+/// [`Undefined`](Self::Undefined), which like a float NaN is unordered and
+/// unequal to everything, itself included.
+#[derive(Clone, Copy)]
+pub enum ExtendedRational {
+    Finite(Rational),
+    /// `1/0`, above every finite value.
+    Infinity,
+    /// `-1/0`, below every finite value.
+    NegInfinity,
+    /// `0/0` and the other forms without a value.
+    Undefined,
+}
+
+use ExtendedRational::{Finite, Infinity, NegInfinity, Undefined};
+
+impl ExtendedRational {
+    /// `num/den`, allowing a zero `den`.
+    pub fn new(num: i64, den: i64) -> Self {
+        Self::from(Rational::from(num)) / Self::from(Rational::from(den))
+    }
+
+    /// The value, unless it is infinite or undefined.
+    pub fn finite(self) -> Option<Rational> {
+        match self {
+            Finite(v) => Some(v),
+            _ => None,
+        }
+    }
+
+    pub fn is_infinite(&self) -> bool {
+        matches!(self, Infinity | NegInfinity)
+    }
+
+    pub fn is_undefined(&self) -> bool {
+        matches!(self, Undefined)
+    }
+
+    // the infinity with the sign of `negative`
+    fn infinity(negative: bool) -> Self {
+        if negative { NegInfinity } else { Infinity }
+    }
+
+    // -1, 0 or 1 for anything but `Undefined`
+    fn sign(self) -> Option<Ordering> {
+        match self {
+            Finite(v) => Some(v.numer().cmp(&0)),
+            Infinity => Some(Ordering::Greater),
+            NegInfinity => Some(Ordering::Less),
+            Undefined => None,
+        }
+    }
+}
+
+impl From<Rational> for ExtendedRational {
+    fn from(v: Rational) -> Self {
+        Finite(v)
+    }
+}
+
+impl Default for ExtendedRational {
+    fn default() -> Self {
+        Finite(Rational::default())
+    }
+}
+
+impl PartialEq for ExtendedRational {
+    fn eq(&self, other: &Self) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
+    }
+}
+
+/// Orders `-1/0` below and `1/0` above every finite value. `Undefined` is
+/// unordered.
+impl PartialOrd for ExtendedRational {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        match (self, other) {
+            (Undefined, _) | (_, Undefined) => None,
+            (Finite(a), Finite(b)) => Some(a.cmp(b)),
+            (Infinity, Infinity) | (NegInfinity, NegInfinity) => Some(Ordering::Equal),
+            (Infinity, _) | (_, NegInfinity) => Some(Ordering::Greater),
+            (NegInfinity, _) | (_, Infinity) => Some(Ordering::Less),
+        }
+    }
+}
+
+impl Neg for ExtendedRational {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        match self {
+            Finite(v) => Finite(-v),
+            Infinity => NegInfinity,
+            NegInfinity => Infinity,
+            Undefined => Undefined,
+        }
+    }
+}
+
+impl Add for ExtendedRational {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Finite(a), Finite(b)) => Finite(a + b),
+            (Undefined, _) | (_, Undefined) => Undefined,
+            (Infinity, NegInfinity) | (NegInfinity, Infinity) => Undefined,
+            (Infinity | NegInfinity, _) => self,
+            (_, infinite) => infinite,
+        }
+    }
+}
+
+impl Sub for ExtendedRational {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self + -rhs
+    }
+}
+
+impl Mul for ExtendedRational {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        if let (Finite(a), Finite(b)) = (self, rhs) {
+            return Finite(a * b);
+        }
+        // at least one side is infinite or undefined, so a zero factor
+        // leaves no value
+        match (self.sign(), rhs.sign()) {
+            (Some(a), Some(b)) if a.is_ne() && b.is_ne() => Self::infinity(a != b),
+            _ => Undefined,
+        }
+    }
+}
+
+impl Div for ExtendedRational {
+    type Output = Self;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        match (self, rhs) {
+            (Undefined, _) | (_, Undefined) => Undefined,
+            (Finite(a), Finite(b)) if b.is_zero() => {
+                if a.is_zero() {
+                    Undefined
+                } else {
+                    Self::infinity(a.is_negative())
+                }
+            }
+            (Finite(a), Finite(b)) => Finite(a / b),
+            (Finite(_), _) => Self::default(),
+            (_, Finite(b)) if b.is_zero() => self,
+            (_, Finite(b)) => {
+                if b.is_negative() {
+                    -self
+                } else {
+                    self
+                }
+            }
+            _ => Undefined,
+        }
+    }
+}
+
+impl ExtendedRational {
+    // writes the forms without a finite value, or the finite one with `finite`
+    fn fmt_with(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        finite: fn(&Rational, &mut std::fmt::Formatter<'_>) -> std::fmt::Result,
+    ) -> std::fmt::Result {
+        match self {
+            Finite(v) => finite(v, f),
+            Infinity => f.pad("1/0"),
+            NegInfinity => f.pad("-1/0"),
+            Undefined => f.pad("0/0"),
+        }
+    }
+}
+
+/// Writes finite values like [`Rational`] does, the infinities as `1/0` and
+/// `-1/0` and `Undefined` as `0/0`.
+impl Debug for ExtendedRational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_with(f, Debug::fmt)
+    }
+}
+
+impl std::fmt::Display for ExtendedRational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.fmt_with(f, std::fmt::Display::fmt)
+    }
+}
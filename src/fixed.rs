@@ -0,0 +1,130 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+use crate::{Error, OVERFLOW, Rational, Result};
+
+/// A value with a compile-time denominator, stored as a count of `1/D` units,
+/// e.g. `FixedRatio<10000>` for basis points or `FixedRatio<960>` for MIDI
+/// ticks.
+///
+/// Addition, subtraction and scaling by an integer are always exact, and
+/// their operators panic on overflow like those of [`Rational`].
+/// Multiplying or dividing two values can leave the grid, so those are only
+/// offered as checked operations that fail with [`Error::Inexact`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FixedRatio<const D: i64>(i64);
+
+impl<const D: i64> FixedRatio<D> {
+    const VALID: () = assert!(D > 0, "FixedRatio denominator must be positive");
+
+    /// The value `units / D`.
+    pub const fn from_units(units: i64) -> Self {
+        let () = Self::VALID;
+        Self(units)
+    }
+
+    /// The number of `1/D` units.
+    pub const fn units(self) -> i64 {
+        self.0
+    }
+
+    /// The sum, failing with [`Error::Overflow`] if it does not fit.
+    pub fn checked_add(self, rhs: Self) -> Result<Self> {
+        self.0
+            .checked_add(rhs.0)
+            .map(Self)
+            .ok_or(Error::Overflow { span: None })
+    }
+
+    /// The difference, failing with [`Error::Overflow`] if it does not fit.
+    pub fn checked_sub(self, rhs: Self) -> Result<Self> {
+        self.0
+            .checked_sub(rhs.0)
+            .map(Self)
+            .ok_or(Error::Overflow { span: None })
+    }
+
+    /// The product, failing with [`Error::Inexact`] if it is not a multiple
+    /// of `1/D`.
+    pub fn checked_mul(self, rhs: Self) -> Result<Self> {
+        Self::from_scaled(self.0 as i128 * rhs.0 as i128, D as i128)
+    }
+
+    /// The quotient, failing with [`Error::Inexact`] if it is not a multiple
+    /// of `1/D`.
+    pub fn checked_div(self, rhs: Self) -> Result<Self> {
+        if rhs.0 == 0 {
+            return Err(Error::DivisionByZero);
+        }
+        Self::from_scaled(self.0 as i128 * D as i128, rhs.0 as i128)
+    }
+
+    // the value (num / den) / D
+    fn from_scaled(num: i128, den: i128) -> Result<Self> {
+        if num % den != 0 {
+            let value = Rational::from_i128(num, den * D as i128);
+            return Err(value.map_or(Error::Overflow { span: None }, Error::Inexact));
+        }
+        i64::try_from(num / den)
+            .map(Self)
+            .map_err(|_| Error::Overflow { span: None })
+    }
+}
+
+impl<const D: i64> From<FixedRatio<D>> for Rational {
+    fn from(v: FixedRatio<D>) -> Self {
+        Rational::reduce(v.0, D)
+    }
+}
+
+impl<const D: i64> TryFrom<Rational> for FixedRatio<D> {
+    type Error = Error;
+
+    fn try_from(v: Rational) -> Result<Self> {
+        let () = Self::VALID;
+        let den = v.1.get();
+        if D % den != 0 {
+            return Err(Error::Inexact(v));
+        }
+        v.0.checked_mul(D / den)
+            .map(Self)
+            .ok_or(Error::Overflow { span: None })
+    }
+}
+
+impl<const D: i64> Neg for FixedRatio<D> {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self(self.0.checked_neg().expect(OVERFLOW))
+    }
+}
+
+impl<const D: i64> Add for FixedRatio<D> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs).expect(OVERFLOW)
+    }
+}
+
+impl<const D: i64> Sub for FixedRatio<D> {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.checked_sub(rhs).expect(OVERFLOW)
+    }
+}
+
+impl<const D: i64> Mul<i64> for FixedRatio<D> {
+    type Output = Self;
+
+    fn mul(self, rhs: i64) -> Self::Output {
+        Self(self.0.checked_mul(rhs).expect(OVERFLOW))
+    }
+}
+
+impl<const D: i64> std::fmt::Display for FixedRatio<D> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&Rational::from(*self), f)
+    }
+}
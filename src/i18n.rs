@@ -0,0 +1,58 @@
+//! Translated error messages.
+
+use crate::Error;
+
+/// A language messages can be written in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lang {
+    #[default]
+    En,
+    Nl,
+}
+
+impl Lang {
+    /// The language of a locale like `nl_NL.UTF-8` or a plain code like
+    /// `nl`, if it is supported.
+    pub fn from_locale(locale: &str) -> Option<Self> {
+        let code = locale.split(['_', '-', '.', '@']).next()?;
+        match code.to_ascii_lowercase().as_str() {
+            "en" | "c" | "posix" => Some(Lang::En),
+            "nl" => Some(Lang::Nl),
+            _ => None,
+        }
+    }
+}
+
+impl Error {
+    /// The message in `lang`; English is the same as [`Display`](std::fmt::Display).
+    pub fn localized(&self, lang: Lang) -> String {
+        match lang {
+            Lang::En => self.to_string(),
+            Lang::Nl => match self {
+                Error::DivisionByZero => "deling door nul".to_string(),
+                Error::InvalidSyntax(index) => format!("ongeldige syntaxis op positie {index}"),
+                Error::InvalidExpr => "ongeldige expressie".to_string(),
+                Error::NeedMoreInput => "onvolledige expressie".to_string(),
+                Error::NotAnInteger(v) => format!("{v} is geen geheel getal"),
+                Error::Inexact(v) => format!("{v} kan niet exact worden weergegeven"),
+                Error::UnknownFunction(name) => format!("onbekende functie `{name}`"),
+                Error::UnknownVariable(name) => format!("onbekende variabele `{name}`"),
+                Error::FunctionFailed { name, code } => {
+                    format!("functie `{name}` mislukte met code {code}")
+                }
+                Error::IrrationalRoot { value, degree: 2 } => {
+                    format!("de vierkantswortel van {value} is niet rationaal")
+                }
+                Error::IrrationalRoot { value, degree } => {
+                    format!("de {degree}e-machtswortel van {value} is niet rationaal")
+                }
+                Error::ZeroToNegativePower => "nul tot een negatieve macht".to_string(),
+                Error::NoValues => "geen waarden".to_string(),
+                Error::Overflow { span: None } => "rekenkundige overloop".to_string(),
+                Error::Overflow { span: Some(span) } => {
+                    format!("rekenkundige overloop bij {}..{}", span.start, span.end)
+                }
+            },
+        }
+    }
+}
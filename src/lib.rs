@@ -1,209 +1,2502 @@
 use core::panic;
+use std::cmp::Ordering;
 use std::fmt::Debug;
-use std::iter::Product;
-use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub};
-use std::sync::Mutex;
+use std::hash::{Hash, Hasher};
+use std::iter::{Product, Sum};
+use std::num::NonZeroI64;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Range, Rem, Sub, SubAssign};
+use std::str::FromStr;
+use std::time::Duration;
 
-static PRIMES: Mutex<Vec<u64>> = Mutex::new(Vec::new());
+#[cfg(feature = "approx")]
+mod approx_eq;
+#[cfg(feature = "proptest")]
+mod arbitrary;
+mod backend;
+#[cfg(feature = "bigint")]
+mod big;
+mod context;
+mod diagnostics;
+mod display;
+mod ext;
+mod extended;
+mod fixed;
+#[cfg(feature = "i18n")]
+pub mod i18n;
+pub mod plugin;
+mod rational32;
+#[cfg(feature = "serde")]
+pub mod serde;
+mod simplify;
+pub mod stats;
+#[cfg(feature = "testing")]
+pub mod testing;
+
+pub use backend::Integer;
+#[cfg(feature = "bigint")]
+pub use big::BigRational;
+pub use context::Context;
+pub use diagnostics::Diagnostics;
+pub use display::{Improper, Latex, LatexOptions, Mixed, Scaled, Unicode};
+pub use ext::RatioExt;
+pub use extended::ExtendedRational;
+pub use fixed::FixedRatio;
+pub use simplify::{SimplifyStep, simplify_steps};
 
 #[derive(Debug)]
 pub enum Error {
     DivisionByZero,
     InvalidSyntax(usize),
     InvalidExpr,
+    /// The input ended inside parentheses or after an operator, so more of
+    /// it could still make a valid expression.
+    NeedMoreInput,
+    NotAnInteger(Rational),
+    /// The exact result cannot be represented by the requested type.
+    Inexact(Rational),
+    UnknownFunction(String),
+    /// A name in the expression that the [`Context`] does not define.
+    UnknownVariable(String),
+    /// A function from a [`Context`] returned the nonzero error `code`.
+    FunctionFailed {
+        name: String,
+        code: i32,
+    },
+    /// A root was taken whose result is not rational.
+    IrrationalRoot {
+        value: Rational,
+        degree: u32,
+    },
+    /// Zero raised to a negative power.
+    ZeroToNegativePower,
+    /// A statistic of an empty set of values, see [`stats`].
+    NoValues,
+    /// An intermediate result did not fit in the integer backend; `span` is
+    /// the part of the expression that overflowed, if known.
+    Overflow {
+        span: Option<Range<usize>>,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::DivisionByZero => write!(f, "division by zero"),
+            Error::InvalidSyntax(index) => write!(f, "invalid syntax at position {index}"),
+            Error::InvalidExpr => write!(f, "invalid expression"),
+            Error::NeedMoreInput => write!(f, "incomplete expression"),
+            Error::NotAnInteger(v) => write!(f, "{v} is not an integer"),
+            Error::Inexact(v) => write!(f, "{v} cannot be represented exactly"),
+            Error::UnknownFunction(name) => write!(f, "unknown function `{name}`"),
+            Error::UnknownVariable(name) => write!(f, "unknown variable `{name}`"),
+            Error::FunctionFailed { name, code } => {
+                write!(f, "function `{name}` failed with code {code}")
+            }
+            Error::IrrationalRoot { value, degree: 2 } => {
+                write!(f, "the square root of {value} is not rational")
+            }
+            Error::IrrationalRoot { value, degree } => {
+                write!(f, "the root of degree {degree} of {value} is not rational")
+            }
+            Error::ZeroToNegativePower => write!(f, "zero raised to a negative power"),
+            Error::NoValues => write!(f, "no values"),
+            Error::Overflow { span: None } => write!(f, "arithmetic overflow"),
+            Error::Overflow { span: Some(span) } => {
+                write!(f, "arithmetic overflow at {}..{}", span.start, span.end)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Error {
+    /// A stable identifier of the kind of error, e.g. `"division-by-zero"`,
+    /// for matching on errors whatever language their message is in.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::DivisionByZero => "division-by-zero",
+            Error::InvalidSyntax(_) => "invalid-syntax",
+            Error::InvalidExpr => "invalid-expr",
+            Error::NeedMoreInput => "need-more-input",
+            Error::NotAnInteger(_) => "not-an-integer",
+            Error::Inexact(_) => "inexact",
+            Error::UnknownFunction(_) => "unknown-function",
+            Error::UnknownVariable(_) => "unknown-variable",
+            Error::FunctionFailed { .. } => "function-failed",
+            Error::IrrationalRoot { .. } => "irrational-root",
+            Error::ZeroToNegativePower => "zero-to-negative-power",
+            Error::NoValues => "no-values",
+            Error::Overflow { .. } => "overflow",
+        }
+    }
+
+    // attach the location of the failing subexpression to an overflow
+    fn with_span(self, span: &Range<usize>) -> Self {
+        match self {
+            Error::Overflow { span: None } => Error::Overflow {
+                span: Some(span.clone()),
+            },
+            e => e,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// How [`Rational::snap_with`] picks a fraction for a float.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapOptions {
+    /// How far the simplest fraction may be from the float.
+    pub tolerance: f64,
+    /// The largest denominator to return.
+    pub max_denominator: u64,
+}
+
+impl Default for SnapOptions {
+    /// A tolerance of `1e-4` and denominators up to 1000.
+    fn default() -> Self {
+        Self {
+            tolerance: 1e-4,
+            max_denominator: 1000,
+        }
+    }
+}
+
+/// The decimal expansion of a [`Rational`], split into the digits before
+/// the repeating part and the repeating part itself, from
+/// [`Rational::to_repeating_decimal`]. Displays like `0.1(6)`, or with no
+/// parentheses when the expansion terminates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepeatingDecimal {
+    pub negative: bool,
+    /// The digits before the point.
+    pub int: u64,
+    /// The fractional digits before the period, e.g. `[1]` for `1/6`.
+    pub pre_period: Vec<u8>,
+    /// The digits that repeat forever, e.g. `[6]` for `1/6`, empty when the
+    /// expansion terminates.
+    pub period: Vec<u8>,
+}
+
+impl std::fmt::Display for RepeatingDecimal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let digits = |d: &[u8]| d.iter().map(|d| char::from(b'0' + d)).collect::<String>();
+        if self.negative {
+            write!(f, "-")?;
+        }
+        write!(f, "{}", self.int)?;
+        if !self.pre_period.is_empty() || !self.period.is_empty() {
+            write!(f, ".{}", digits(&self.pre_period))?;
+        }
+        if !self.period.is_empty() {
+            write!(f, "({})", digits(&self.period))?;
+        }
+        Ok(())
+    }
+}
+
+/// How [`Rational::to_egyptian_with`] splits a fraction into unit fractions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EgyptianMethod {
+    /// Take the largest unit fraction that fits each time. Few terms, but
+    /// the denominators can grow doubly exponentially.
+    #[default]
+    Greedy,
+    /// Split off the difference to the Farey neighbour below, which keeps
+    /// every denominator of `a/b` below `b^2` at the cost of up to `a`
+    /// terms.
+    Farey,
+}
+
+/// How to resolve a value that falls between two representable results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RoundingMode {
+    /// Towards negative infinity.
+    Floor,
+    /// Towards positive infinity.
+    Ceil,
+    /// Drop the fractional part.
+    TowardZero,
+    /// Away from zero, to the next result whenever any part is dropped.
+    AwayFromZero,
+    /// To the nearest result, ties away from zero.
+    HalfUp,
+    /// To the nearest result, ties towards zero.
+    HalfDown,
+    /// To the nearest result, ties to the even neighbour.
+    HalfEven,
+}
+
+impl RoundingMode {
+    // whether a truncated magnitude should be bumped by one unit, `half` being
+    // the discarded remainder compared to half a unit
+    fn rounds_away(self, negative: bool, inexact: bool, half: Ordering, odd: bool) -> bool {
+        match self {
+            Self::Floor => negative && inexact,
+            Self::Ceil => !negative && inexact,
+            Self::TowardZero => false,
+            Self::AwayFromZero => inexact,
+            Self::HalfUp => half != Ordering::Less,
+            Self::HalfDown => half == Ordering::Greater,
+            Self::HalfEven => half == Ordering::Greater || (half == Ordering::Equal && odd),
+        }
+    }
+}
+
+// `num/den` written in `radix` with `digits` fractional digits, the last one
+// rounded using `mode`, see `Rational::to_decimal_string`. Ties are only
+// rounded to even in even radixes, where the last digit has the parity of
+// the value.
+fn positional_string(
+    negative: bool,
+    num: u128,
+    den: u128,
+    radix: u32,
+    digits: usize,
+    mode: RoundingMode,
+) -> String {
+    let mut int = num / den;
+    let mut rem = num % den;
+    let mut frac = Vec::with_capacity(digits);
+    for _ in 0..digits {
+        let (digit, next) = next_digit(rem, den, radix);
+        frac.push(digit);
+        rem = next;
+    }
+
+    let odd = frac.last().map_or(int % 2 == 1, |d| d % 2 == 1);
+    if mode.rounds_away(negative, rem != 0, (2 * rem).cmp(&den), odd) {
+        match frac.iter().rposition(|&d| d != radix - 1) {
+            Some(pos) => {
+                frac[pos] += 1;
+                frac[pos + 1..].fill(0);
+            }
+            None => {
+                frac.fill(0);
+                int += 1;
+            }
+        }
+    }
+
+    let mut out = String::new();
+    if negative && (int != 0 || frac.iter().any(|&d| d != 0)) {
+        out.push('-');
+    }
+    out.push_str(&radix_digits(int, radix));
+    if digits > 0 {
+        out.push('.');
+        out.extend(frac.iter().map(|&d| char::from_digit(d, radix).unwrap()));
+    }
+    out
+}
+
+// the next digit and remainder of `rem / den` in `radix` for `rem < den`,
+// also when `radix * rem` does not fit
+fn next_digit(rem: u128, den: u128, radix: u32) -> (u32, u128) {
+    if let Some(wide) = rem.checked_mul(radix as u128) {
+        return ((wide / den) as u32, wide % den);
+    }
+    // acc and rem are both below den, which is at most 2^127
+    let (mut digit, mut acc) = (0, 0);
+    for _ in 0..radix {
+        acc += rem;
+        if acc >= den {
+            acc -= den;
+            digit += 1;
+        }
+    }
+    (digit, acc)
+}
+
+fn round_magnitude(negative: bool, num: u128, den: u128, mode: RoundingMode) -> u128 {
+    let (quot, rem) = (num / den, num % den);
+    if mode.rounds_away(negative, rem != 0, (2 * rem).cmp(&den), quot % 2 == 1) {
+        quot + 1
+    } else {
+        quot
+    }
+}
+
+/// An exact fraction with parts of the integer type `T`, usually used
+/// through the aliases [`Rational`], [`Rational32`] and [`Rational128`].
+///
+/// Every operation returns the canonical form: numerator and denominator are
+/// coprime and the denominator is positive, so the sign is carried by the
+/// numerator and zero is always `0/1`. The denominator is stored as a
+/// nonzero integer, so `Option<Rational>` is no larger than `Rational`.
+///
+/// Every width offers the core arithmetic, comparisons and formatting, see
+/// [`Integer`]. Everything else, like parsing and evaluating expressions, is
+/// i64 only, and [`Rational32`] mirrors most of it on the widened value.
+///
+/// The operators work on wider intermediates and panic, in release builds
+/// as well, if the reduced result does not fit; the `checked_*` methods
+/// return [`Error::Overflow`] instead and the `saturating_*` ones clamp.
 #[derive(Clone, Copy)]
-pub struct Rational(i64, i64);
+pub struct Ratio<T: Integer>(T, T::NonZero);
+
+/// The default [`Ratio`] with i64 parts.
+pub type Rational = Ratio<i64>;
+
+/// A [`Ratio`] with i32 parts, 8 bytes in total.
+pub type Rational32 = Ratio<i32>;
+
+/// A [`Ratio`] with i128 parts, for sums whose denominators outgrow i64.
+pub type Rational128 = Ratio<i128>;
+
+const NZ_ONE: NonZeroI64 = NonZeroI64::new(1).unwrap();
+
+const OVERFLOW: &str = "Rational overflow";
+
+/// A [`Rational`] from an integer or fraction literal, e.g. `rational!(3 / 4)`,
+/// `rational!(-6 / 8)` or `rational!(-7)`, reduced at compile time. The
+/// expansion is a constant, so it can be used in `const` and `static` items,
+/// and a zero denominator fails to compile.
+#[macro_export]
+macro_rules! rational {
+    ($num:literal / $den:literal) => {
+        const { $crate::Rational::reduce_const($num, $den) }
+    };
+    ($num:literal) => {
+        const { $crate::Rational::from_integer($num) }
+    };
+}
+
+impl<T: Integer> Ratio<T> {
+    /// Adapts the value to be displayed as an improper fraction, e.g. `7/2`
+    /// rather than `3+1/2`.
+    pub fn display_improper(self) -> Improper<T> {
+        Improper(self)
+    }
+
+    /// Adapts the value to be displayed as a mixed number with a space
+    /// between the parts, e.g. `3 1/2` or `-3 1/2`.
+    pub fn display_mixed(self) -> Mixed<T> {
+        Mixed(self)
+    }
+
+    /// Adapts the value to be displayed with Unicode fraction glyphs, e.g.
+    /// `3½` for `7/2` and `⁷⁄₁₂` for `7/12` where there is no precomposed
+    /// glyph.
+    pub fn display_unicode(self) -> Unicode<T> {
+        Unicode(self)
+    }
+
+    /// The value as LaTeX math, e.g. `\frac{3}{4}`, `-\frac{3}{4}` or `2`.
+    /// See [`display_latex`](Self::display_latex) for mixed numbers and
+    /// `\tfrac`.
+    pub fn to_latex(self) -> String {
+        self.display_latex(LatexOptions::default()).to_string()
+    }
+
+    /// Adapts the value to be displayed as LaTeX math written according to
+    /// `opts`, e.g. `-3\tfrac{1}{2}` for `-7/2` as a mixed `\tfrac`.
+    pub fn display_latex(self, opts: LatexOptions) -> Latex<T> {
+        Latex(self, opts)
+    }
+
+    /// The value as MathML presentation markup, e.g.
+    /// `<mfrac><mn>3</mn><mn>4</mn></mfrac>` for `3/4`, with a leading
+    /// `<mo>-</mo>` in an `<mrow>` for negative values. It leaves out the
+    /// enclosing `<math>` element so that it can be embedded in a larger
+    /// formula.
+    pub fn to_mathml(self) -> String {
+        let (negative, num, den) = self.sign_magnitude();
+        let magnitude = match den {
+            1 => format!("<mn>{num}</mn>"),
+            _ => format!("<mfrac><mn>{num}</mn><mn>{den}</mn></mfrac>"),
+        };
+        if negative {
+            format!("<mrow><mo>-</mo>{magnitude}</mrow>")
+        } else {
+            magnitude
+        }
+    }
+
+    /// Adapts the value to be displayed in percent with `precision`
+    /// fractional digits, e.g. `1/4` as `25%` or `1/3` as `33.33%` for a
+    /// precision of 2. The last digit is rounded half to even unless
+    /// [`Scaled::rounding`] picks another mode.
+    pub fn display_percent(self, precision: usize) -> Scaled<T> {
+        self.display_scaled(2, "%", precision)
+    }
+
+    /// Adapts the value to be displayed in per-mille with `precision`
+    /// fractional digits, e.g. `1/4` as `250‰`, like
+    /// [`display_percent`](Self::display_percent).
+    pub fn display_permille(self, precision: usize) -> Scaled<T> {
+        self.display_scaled(3, "‰", precision)
+    }
+
+    fn display_scaled(self, exp: usize, suffix: &'static str, digits: usize) -> Scaled<T> {
+        Scaled {
+            value: self,
+            exp,
+            suffix,
+            digits,
+            mode: RoundingMode::HalfEven,
+        }
+    }
+
+    // sign and the absolute values of numerator and denominator
+    fn sign_magnitude(&self) -> (bool, u128, u128) {
+        (
+            self.0 < T::ZERO,
+            self.0.unsigned_abs(),
+            T::get(self.1).unsigned_abs(),
+        )
+    }
+
+    /// The canonical form of `num/den`, e.g. `new(2, -4)` is `-1/2`. Fails
+    /// with [`Error::DivisionByZero`] if `den` is zero and with
+    /// [`Error::Overflow`] for `new(T::MIN, -1)`.
+    pub fn new(num: T, den: T) -> Result<Self> {
+        if den == T::ZERO {
+            return Err(Error::DivisionByZero);
+        }
+        T::canonical(num, den).ok_or(Error::Overflow { span: None })
+    }
+
+    /// The numerator, which carries the sign.
+    pub fn numer(&self) -> T {
+        self.0
+    }
+
+    /// The denominator, always positive.
+    pub fn denom(&self) -> T {
+        T::get(self.1)
+    }
+
+    /// The numerator and denominator of the canonical form.
+    pub fn into_parts(self) -> (T, T) {
+        (self.0, T::get(self.1))
+    }
+
+    /// Whether the value is in canonical form, see [`Rational`].
+    pub fn is_canonical(&self) -> bool {
+        // checked directly, as the constructors assert it
+        let den = T::get(self.1);
+        den > T::ZERO && gcd128(self.0.unsigned_abs(), den.unsigned_abs()) == 1
+    }
+
+    /// Whether numerator and denominator are coprime, the same as
+    /// [`is_canonical`](Self::is_canonical).
+    pub fn is_reduced(&self) -> bool {
+        self.is_canonical()
+    }
+
+    /// Whether the denominator is 1.
+    pub fn is_integer(&self) -> bool {
+        T::get(self.1) == T::ONE
+    }
+
+    /// Whether the magnitude is below 1, `|num| < den`, e.g. `-3/4` but not
+    /// `4/3` or `1`.
+    pub fn is_proper(&self) -> bool {
+        self.0 / T::get(self.1) == T::ZERO
+    }
+
+    /// The absolute value. Panics if the numerator is `T::MIN`.
+    pub fn abs(self) -> Self {
+        if self.is_negative() { -self } else { self }
+    }
+
+    /// `-1`, `0` or `1` with the sign of the value.
+    pub fn signum(self) -> Self {
+        let one = Self::from(T::ONE);
+        match self.0.cmp(&T::ZERO) {
+            Ordering::Less => -one,
+            Ordering::Equal => Self::default(),
+            Ordering::Greater => one,
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.0 < T::ZERO
+    }
+
+    pub fn is_positive(&self) -> bool {
+        self.0 > T::ZERO
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == T::ZERO
+    }
+
+    /// The exact sum, failing with [`Error::Overflow`] if it does not fit.
+    pub fn checked_add(self, other: Self) -> Result<Self> {
+        T::add_rational(self, other).ok_or(Error::Overflow { span: None })
+    }
+
+    /// The exact difference, failing with [`Error::Overflow`] if it does not
+    /// fit.
+    pub fn checked_sub(self, other: Self) -> Result<Self> {
+        T::sub_rational(self, other).ok_or(Error::Overflow { span: None })
+    }
+
+    /// The exact product, failing with [`Error::Overflow`] if it does not
+    /// fit.
+    pub fn checked_mul(self, other: Self) -> Result<Self> {
+        T::mul_rational(self, other).ok_or(Error::Overflow { span: None })
+    }
+
+    /// The exact quotient, failing with [`Error::DivisionByZero`] or with
+    /// [`Error::Overflow`] if it does not fit.
+    pub fn checked_div(self, other: Self) -> Result<Self> {
+        if other.0 == T::ZERO {
+            return Err(Error::DivisionByZero);
+        }
+        T::div_rational(self, other).ok_or(Error::Overflow { span: None })
+    }
+
+    /// Divides in place like `/=`, but fails with [`Error::DivisionByZero`]
+    /// or [`Error::Overflow`] instead of panicking, leaving `self` unchanged.
+    pub fn checked_div_assign(&mut self, rhs: Self) -> Result<()> {
+        *self = self.checked_div(rhs)?;
+        Ok(())
+    }
+
+    /// The reciprocal `1/self`, failing with [`Error::DivisionByZero`] for
+    /// zero or with [`Error::Overflow`] for a numerator of `T::MIN`.
+    pub fn recip(self) -> Result<Self> {
+        Self::from(T::ONE).checked_div(self)
+    }
+
+    /// Compares two values exactly for any pair, including at the extremes
+    /// of `T`. For i64, values of different sign or with the same
+    /// denominator are told apart without multiplying and otherwise the cross
+    /// products are formed in i128; the other backends compare continued
+    /// fractions, which never overflows.
+    pub fn total_cmp(&self, other: &Self) -> Ordering {
+        T::cmp_rational(self, other)
+    }
+}
+
+impl Rational {
+    pub const ZERO: Self = Self::from_integer(0);
+    pub const ONE: Self = Self::from_integer(1);
+    pub const NEG_ONE: Self = Self::from_integer(-1);
+
+    /// The integer `v` as `v/1`, like `From<i64>` but usable in constants
+    /// and statics.
+    pub const fn from_integer(v: i64) -> Self {
+        Self(v, NZ_ONE)
+    }
+
+    // the canonical form of num/den at compile time, for `rational!`
+    #[doc(hidden)]
+    pub const fn reduce_const(num: i64, den: i64) -> Self {
+        if den == 0 {
+            panic!("cannot divide by zero");
+        }
+        let g = gcd(num.unsigned_abs(), den.unsigned_abs()) as i128;
+        let (num, den) = (num as i128 / g, den as i128 / g);
+        let (num, den) = if den < 0 { (-num, -den) } else { (num, den) };
+        if num > i64::MAX as i128 || den > i64::MAX as i128 {
+            panic!("{}", OVERFLOW);
+        }
+        Self::new_unchecked(num as i64, den as i64)
+    }
+
+    /// `num/den` as given, for constants and statics. The caller must pass
+    /// the canonical form, a positive `den` coprime with `num`, which is only
+    /// checked in debug builds; otherwise comparisons and hashing are wrong.
+    pub const fn new_unchecked(num: i64, den: i64) -> Self {
+        debug_assert!(
+            den > 0 && gcd(num.unsigned_abs(), den as u64) == 1,
+            "Rational::new_unchecked needs a canonical fraction"
+        );
+        match NonZeroI64::new(den) {
+            Some(den) => Self(num, den),
+            None => panic!("cannot divide by zero"),
+        }
+    }
+
+    // canonical form of num/den, panicking if den is zero
+    fn reduce(num: i64, den: i64) -> Self {
+        if den == 0 {
+            panic!("cannot divide by zero");
+        }
+        let gcd = gcd(num.unsigned_abs(), den.unsigned_abs()) as i64;
+        let (a, b) = (num / gcd, den / gcd);
+        let (a, b) = if b < 0 { (-a, -b) } else { (a, b) };
+        let v = Self(a, NonZeroI64::new(b).unwrap());
+        debug_assert!(v.is_canonical());
+        v
+    }
+
+    // canonical form of num/den if it fits, den being nonzero
+    fn from_i128(num: i128, den: i128) -> Option<Self> {
+        let g = gcd128(num.unsigned_abs(), den.unsigned_abs()) as i128;
+        let (num, den) = if den < 0 {
+            (-num / g, -den / g)
+        } else {
+            (num / g, den / g)
+        };
+        let v = Self(
+            i64::try_from(num).ok()?,
+            NonZeroI64::new(i64::try_from(den).ok()?)?,
+        );
+        debug_assert!(v.is_canonical());
+        Some(v)
+    }
+
+    pub fn run_expr(expr: &str) -> Result<Self> {
+        eval(expr, 0, &Context::default(), &mut Trace::default())
+    }
+
+    /// Evaluates `expr` like [`run_expr`](Self::run_expr), looking up the
+    /// names it uses in `ctx`.
+    pub fn run_expr_with_context(expr: &str, ctx: &Context) -> Result<Self> {
+        eval(expr, 0, ctx, &mut Trace::default())
+    }
+
+    /// Evaluates `expr` like [`run_expr_with_context`](Self::run_expr_with_context),
+    /// also reporting the largest intermediate values, e.g. to warn when the
+    /// result came close to overflowing.
+    pub fn run_expr_with_diagnostics(expr: &str, ctx: &Context) -> (Result<Self>, Diagnostics) {
+        let mut trace = Trace::default();
+        let res = eval(expr, 0, ctx, &mut trace);
+        (res, trace.diag)
+    }
+
+    /// Evaluates `expr` like [`run_expr_with_context`](Self::run_expr_with_context),
+    /// also returning the value of every subexpression that was computed,
+    /// with its span of characters, in the order they were computed. On an
+    /// error these are the parts that did succeed, e.g. to highlight the
+    /// failing term while still showing the rest.
+    pub fn run_expr_partial(
+        expr: &str,
+        ctx: &Context,
+    ) -> (Result<Self>, Vec<(Range<usize>, Rational)>) {
+        let mut trace = Trace::default();
+        let res = eval(expr, 0, ctx, &mut trace);
+        (res, trace.values)
+    }
+
+    /// Rounds to an integer using `mode`.
+    pub fn round(self, mode: RoundingMode) -> i64 {
+        let (negative, num, den) = self.sign_magnitude();
+        let rounded = round_magnitude(negative, num, den, mode) as i128;
+        let rounded = if negative { -rounded } else { rounded };
+        i64::try_from(rounded).expect("rounded value overflows i64")
+    }
+
+    /// The largest integer not greater than the value, e.g. -2 for `-3/2`.
+    pub fn floor(self) -> i64 {
+        self.round(RoundingMode::Floor)
+    }
+
+    /// The smallest integer not less than the value, e.g. -1 for `-3/2`.
+    pub fn ceil(self) -> i64 {
+        self.round(RoundingMode::Ceil)
+    }
+
+    // compares with an integer of any width exactly
+    fn cmp_integer(&self, v: i128) -> Ordering {
+        let fract = if self.is_integer() {
+            Ordering::Equal
+        } else {
+            Ordering::Greater
+        };
+        (self.floor() as i128).cmp(&v).then(fract)
+    }
+
+    /// The integer part, dropping the fraction, e.g. -1 for `-3/2`. Use
+    /// [`round`](Self::round) with [`RoundingMode::HalfUp`] to round to the
+    /// nearest integer with ties away from zero.
+    pub fn trunc(self) -> i64 {
+        self.0 / self.1.get()
+    }
+
+    /// The exact fractional part, `self - self.trunc()`, which has the sign
+    /// of the value, e.g. `-1/2` for `-3/2`.
+    pub fn fract(self) -> Rational {
+        // dividing out the integer part keeps numerator and denominator coprime
+        Ratio(self.0 % self.1.get(), self.1)
+    }
+
+    /// The whole part and the proper fraction that remains, both with the
+    /// sign of the value, e.g. `(2, 3/4)` for `11/4` and `(-2, -3/4)` for
+    /// `-11/4`. This is the split [`Display`](std::fmt::Display) writes as
+    /// `2+3/4`.
+    pub fn to_mixed(self) -> (i64, Rational) {
+        (self.trunc(), self.fract())
+    }
+
+    /// `(a+c)/(b+d)` for `a/b` and `c/d` in canonical form, which lies
+    /// between them, the step of a Stern–Brocot search. Panics if it does not
+    /// fit.
+    pub fn mediant(self, other: Self) -> Rational {
+        let num = self.0 as i128 + other.0 as i128;
+        let den = self.1.get() as i128 + other.1.get() as i128;
+        Self::from_i128(num, den).expect(OVERFLOW)
+    }
+
+    /// Rounds to the nearest multiple of `1/den` using `mode`. Panics if `den`
+    /// is zero or the reduced result does not fit.
+    pub fn round_to_denominator(self, den: u64, mode: RoundingMode) -> Rational {
+        if den == 0 {
+            panic!("cannot round to a zero denominator");
+        }
+        let (negative, num, self_den) = self.sign_magnitude();
+        let rounded = round_magnitude(negative, num * den as u128, self_den, mode) as i128;
+        let rounded = if negative { -rounded } else { rounded };
+        Self::from_i128(rounded, den as i128).expect(OVERFLOW)
+    }
+
+    /// The decimal digits after the point of `|self|`, computed lazily and
+    /// without end, e.g. `1, 4, 2, 8, 5, 7, 1, ...` for `1/7` and `5, 0, 0,
+    /// ...` for `-3/2`. The part before the point is
+    /// [`trunc`](Self::trunc).
+    pub fn digits(self) -> impl Iterator<Item = u8> {
+        let (_, num, den) = self.sign_magnitude();
+        let mut rem = num % den;
+        std::iter::repeat_with(move || {
+            rem *= 10;
+            let digit = (rem / den) as u8;
+            rem %= den;
+            digit
+        })
+    }
+
+    /// Formats as a decimal with exactly `digits` fractional digits, rounding
+    /// the last one using `mode`.
+    pub fn to_decimal_string(self, digits: usize, mode: RoundingMode) -> String {
+        let (negative, num, den) = self.sign_magnitude();
+        positional_string(negative, num, den, 10, digits, mode)
+    }
+
+    /// Formats the reduced numerator and denominator in `radix`, e.g. `31/64`
+    /// as `1f/40` in hex. Integers are written without a denominator. The
+    /// `{:x}`, `{:X}`, `{:o}` and `{:b}` formats do the same for their radix
+    /// and add prefixes like `0x1f/0x40` in the alternate form `{:#x}`.
+    ///
+    /// Panics if `radix` is not in `2..=36`.
+    pub fn to_radix_fraction_string(self, radix: u32) -> String {
+        assert!((2..=36).contains(&radix), "radix must be in 2..=36");
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let num = radix_digits(self.0.unsigned_abs().into(), radix);
+        match self.1.get() {
+            1 => format!("{sign}{num}"),
+            den => format!("{sign}{num}/{}", radix_digits(den as u128, radix)),
+        }
+    }
+
+    /// The decimal expansion with its repeating part, e.g. `1/6` is
+    /// `0.1(6)`, or `None` if the digits before the period and one period
+    /// together are more than `max_digits`. A denominator `d` can need up to
+    /// `d - 1` of them.
+    pub fn to_repeating_decimal(self, max_digits: usize) -> Option<RepeatingDecimal> {
+        let (negative, num, den) = self.sign_magnitude();
+        // the period starts after as many digits as the larger power of 2 or 5
+        // in the denominator
+        let (twos, fives) = (
+            den.trailing_zeros(),
+            (1..).take_while(|&k| den % 5u128.pow(k) == 0).count(),
+        );
+        let pre_len = (twos as usize).max(fives);
+        if pre_len > max_digits {
+            return None;
+        }
+        let mut rem = num % den;
+        let next = |rem: &mut u128| {
+            *rem *= 10;
+            let digit = (*rem / den) as u8;
+            *rem %= den;
+            digit
+        };
+        let pre_period: Vec<u8> = (0..pre_len).map(|_| next(&mut rem)).collect();
+        let start = rem;
+        let mut period = Vec::new();
+        while rem != 0 && (period.is_empty() || rem != start) {
+            if pre_len + period.len() == max_digits {
+                return None;
+            }
+            period.push(next(&mut rem));
+        }
+        Some(RepeatingDecimal {
+            negative,
+            int: (num / den) as u64,
+            pre_period,
+            period,
+        })
+    }
+
+    /// The exact positional expansion in `base`, with a repeating part in
+    /// parentheses, e.g. `1/3` in base 2 is `0.(01)` and `1/6` in base 10 is
+    /// `0.1(6)`. Expansions that need more than `max_digits` fractional
+    /// digits are cut off and end in `...`.
+    ///
+    /// Panics if `base` is not in `2..=36`.
+    pub fn to_radix_string(self, base: u32, max_digits: usize) -> String {
+        assert!((2..=36).contains(&base), "radix must be in 2..=36");
+        let (negative, num, den) = self.sign_magnitude();
+        let mut out = String::new();
+        if negative {
+            out.push('-');
+        }
+        out.push_str(&radix_digits(num / den, base));
+
+        // long division, remembering at which digit each remainder was seen
+        // so that the expansion repeats from there when it comes up again
+        let mut seen = std::collections::HashMap::new();
+        let mut digits = String::new();
+        let mut rem = num % den;
+        while rem != 0 {
+            if let Some(&start) = seen.get(&rem) {
+                digits.insert(start, '(');
+                digits.push(')');
+                break;
+            }
+            if seen.len() == max_digits {
+                digits.push_str("...");
+                break;
+            }
+            seen.insert(rem, digits.len());
+            rem *= base as u128;
+            digits.push(char::from_digit((rem / den) as u32, base).unwrap());
+            rem %= den;
+        }
+        if !digits.is_empty() {
+            out.push('.');
+            out.push_str(&digits);
+        }
+        out
+    }
+
+    /// The square root, if it is rational.
+    pub fn sqrt_exact(&self) -> Option<Self> {
+        self.nth_root_exact(2)
+    }
+
+    /// The real `n`th root, if it is rational. Negative values only have one
+    /// for odd `n`.
+    pub fn nth_root_exact(&self, n: u32) -> Option<Self> {
+        if n == 1 {
+            return Some(*self);
+        }
+        if n == 0 || (self.0 < 0 && n.is_multiple_of(2)) {
+            return None;
+        }
+        // the float estimate is within one of the true root, which is at
+        // most 2^32 here
+        let root = |v: u64| {
+            let guess = (v as f64).powf(1.0 / n as f64).round() as u64;
+            (guess.saturating_sub(1)..=guess + 1).find(|r| r.checked_pow(n) == Some(v))
+        };
+        let num = root(self.0.unsigned_abs())? as i64;
+        let den = root(self.1.get() as u64)? as i64;
+        let num = if self.0 < 0 { -num } else { num };
+        Some(Self(num, NonZeroI64::new(den).unwrap()))
+    }
+
+    /// The integer `k` with `base^k == self`, if there is one, e.g.
+    /// `log_{2/3}(8/27) = 3`. A base of 1 only gives 0 for `self == 1`.
+    pub fn log_exact(&self, base: Rational) -> Option<i64> {
+        let is_one = |v: &Self| v.0 == 1 && v.1 == NZ_ONE;
+        if is_one(self) {
+            return Some(0);
+        }
+        if self.0 == 0 || base.0 == 0 || is_one(&base) {
+            return None;
+        }
+        let above_one = |v: &Self| v.0.unsigned_abs() > v.1.get() as u64;
+        let recip = |v: &Self| Self::reduce(v.1.get(), v.0);
+        if !above_one(&base) && !above_one(&recip(&base)) {
+            // base is -1
+            return (self.0 == -1 && self.1 == NZ_ONE).then_some(1);
+        }
+
+        // find m with g^m = target where |g| > 1, then fix the sign of m
+        let g = if above_one(&base) { base } else { recip(&base) };
+        let target = if above_one(self) { *self } else { recip(self) };
+        let (mut power, mut m) = (g, 1);
+        while power.total_cmp(&target).is_ne() || power.0.signum() != target.0.signum() {
+            if power.0.unsigned_abs() as u128 * target.1.get() as u128
+                > target.0.unsigned_abs() as u128 * power.1.get() as u128
+            {
+                return None;
+            }
+            power = power.checked_mul(g).ok()?;
+            m += 1;
+        }
+        Some(if above_one(self) == above_one(&base) {
+            m
+        } else {
+            -m
+        })
+    }
+
+    /// The exact value of `x`, e.g. `1/10` as a float is
+    /// `3602879701896397/36028797018963968`. `None` if `x` is not finite or
+    /// its numerator or denominator does not fit in an i64, as for very large
+    /// values and for fractions finer than `2^-62`.
+    pub fn from_f64_exact(x: f64) -> Option<Self> {
+        if !x.is_finite() {
+            return None;
+        }
+        if x == 0.0 {
+            return Some(Self::default());
+        }
+        let (mant, exp) = float_parts(x);
+        let zeros = mant.trailing_zeros();
+        let (mant, exp) = ((mant >> zeros) as i128, exp + zeros as i32);
+        let mant = if x < 0.0 { -mant } else { mant };
+        match exp {
+            0..=63 => Self::from_i128(mant << exp, 1),
+            // an odd numerator over a power of two is already canonical
+            -62..0 => Self::from_i128(mant, 1 << -exp),
+            _ => None,
+        }
+    }
+
+    /// The exact value of `x`, see [`from_f64_exact`](Self::from_f64_exact).
+    pub fn from_f32_exact(x: f32) -> Option<Self> {
+        Self::from_f64_exact(x.into())
+    }
+
+    /// The simplest fraction within `eps` of `x`, found by a Stern–Brocot
+    /// search, e.g. `1/3` for `0.3333` with `eps = 0.001`. `None` if `x` is
+    /// not finite, `eps` is negative or NaN, or no representable fraction is
+    /// close enough.
+    pub fn from_f64_with_tolerance(x: f64, eps: f64) -> Option<Self> {
+        if !x.is_finite() || eps.is_nan() || eps < 0.0 {
+            return None;
+        }
+        let (lo, hi) = (x.abs() - eps, x.abs() + eps);
+        if lo <= 0.0 {
+            return Some(Self::default());
+        }
+        // left and right bounds of the current Stern–Brocot interval, taking
+        // as many steps towards [lo, hi] at once as stay outside of it
+        fn advance(
+            from: (i128, i128),
+            toward: (i128, i128),
+            steps: f64,
+            outside: impl Fn(f64) -> bool,
+        ) -> (i128, i128) {
+            let at = |k: i128| (from.0 + k * toward.0, from.1 + k * toward.1);
+            let mut k = (steps.ceil() as i128 - 1).clamp(1, i64::MAX as i128);
+            // the estimate can be a step too far after rounding
+            while k > 1 && !outside(at(k).0 as f64 / at(k).1 as f64) {
+                k -= 1;
+            }
+            at(k)
+        }
+        let (mut left, mut right) = ((0i128, 1i128), (1i128, 0i128));
+        loop {
+            let (num, den) = (left.0 + right.0, left.1 + right.1);
+            if num > i64::MAX as i128 || den > i64::MAX as i128 {
+                return None;
+            }
+            let mediant = num as f64 / den as f64;
+            if mediant < lo {
+                let steps =
+                    (lo * left.1 as f64 - left.0 as f64) / (right.0 as f64 - lo * right.1 as f64);
+                left = advance(left, right, steps, |v| v < lo);
+            } else if mediant > hi {
+                let steps =
+                    (right.0 as f64 - hi * right.1 as f64) / (hi * left.1 as f64 - left.0 as f64);
+                right = advance(right, left, steps, |v| v > hi);
+            } else {
+                let num = if x < 0.0 { -num } else { num };
+                return Self::from_i128(num, den);
+            }
+        }
+    }
+
+    /// The "nicest" fraction for a noisy measurement, with the default
+    /// [`SnapOptions`]: `0.49997` snaps to `1/2`, while `0.4871` is too far
+    /// from it and becomes `19/39`.
+    ///
+    /// Panics if `x` is not finite or does not fit in an i64.
+    pub fn snap(x: f64) -> Self {
+        Self::snap_with(x, SnapOptions::default())
+    }
+
+    /// The simplest fraction within `opts.tolerance` of `x` if its
+    /// denominator is at most `opts.max_denominator`, and otherwise
+    /// [`approximate(x, opts.max_denominator)`](Self::approximate).
+    ///
+    /// Panics if `x` is not finite or does not fit in an i64, or
+    /// `opts.max_denominator` is 0.
+    pub fn snap_with(x: f64, opts: SnapOptions) -> Self {
+        if let Some(v) = Self::from_f64_with_tolerance(x, opts.tolerance)
+            && v.1.get() as u64 <= opts.max_denominator
+        {
+            return v;
+        }
+        Self::approximate(x, opts.max_denominator)
+    }
+
+    /// The fraction closest to `x` with a denominator of at most
+    /// `max_denominator`, e.g. `1/3` for `0.3333` and a bound of 100, found
+    /// from the continued fraction of `x`.
+    ///
+    /// Panics if `x` is not finite or does not fit in an i64, or
+    /// `max_denominator` is 0.
+    pub fn approximate(x: f64, max_denominator: u64) -> Self {
+        assert!(
+            x.is_finite() && x.abs() < i64::MAX as f64,
+            "cannot approximate {x} by a Rational"
+        );
+        assert!(max_denominator > 0, "max_denominator must be positive");
+        let max_den = max_denominator.min(i64::MAX as u64) as i128;
+
+        // the last convergent of the continued fraction of |x| within the
+        // bound, or the semiconvergent after it if that is closer
+        let target = x.abs();
+        let (mut prev, mut cur) = ((0i128, 1i128), (1i128, 0i128));
+        let mut rest = target;
+        let (num, den) = loop {
+            let a = rest.floor();
+            if prev.1 as f64 + a * cur.1 as f64 > max_den as f64 {
+                let k = (max_den - prev.1) / cur.1;
+                let semi = (prev.0 + k * cur.0, prev.1 + k * cur.1);
+                let dist = |(num, den): (i128, i128)| (num as f64 / den as f64 - target).abs();
+                break if dist(semi) < dist(cur) { semi } else { cur };
+            }
+            let a = a as i128;
+            (prev, cur) = (cur, (prev.0 + a * cur.0, prev.1 + a * cur.1));
+            if rest == a as f64 {
+                break cur;
+            }
+            rest = 1.0 / (rest - a as f64);
+        };
+        let num = if x < 0.0 { -num } else { num };
+        Self::from_i128(num, den).expect("approximation fits in i64")
+    }
+
+    /// The closest fractions strictly below and above the value with a
+    /// denominator of at most `max_den`, its neighbours in the Farey
+    /// sequence of that order, e.g. `(1/3, 1/2)` for `2/5` and a bound of 4.
+    ///
+    /// Panics if `max_den` is 0 or a neighbour does not fit.
+    pub fn farey_neighbors(self, max_den: u64) -> (Rational, Rational) {
+        assert!(max_den > 0, "max_den must be positive");
+        let max = max_den.min(i64::MAX as u64) as i128;
+        // the neighbours of the fractional part n/d, shifted back by the
+        // floor, so that every part stays below max_den
+        let (floor, d) = (self.floor() as i128, self.1.get() as i128);
+        let n = self.0 as i128 - floor * d;
+        let (lo, hi) = if d <= max {
+            // the neighbours a/b and c/e of n/d are the fractions with
+            // n*b - a*d = 1 and c*d - n*e = 1, so b is the largest
+            // denominator within the bound that is n^-1 modulo d
+            let inv = mod_inverse(n, d);
+            let b = inv + (max - inv) / d * d;
+            let e = (d - inv) % d;
+            let e = e + (max - e) / d * d;
+            (((n * b - 1) / d, b), ((n * e + 1) / d, e))
+        } else {
+            // descend the Stern–Brocot tree, taking as many steps in one
+            // direction at once as keep the value between the bounds
+            let (mut lo, mut hi) = ((0i128, 1i128), (1i128, 1i128));
+            loop {
+                let below = n * lo.1 - lo.0 * d;
+                let above = hi.0 * d - n * hi.1;
+                let k = ((above - 1) / below).min((max - hi.1) / lo.1);
+                hi = (hi.0 + k * lo.0, hi.1 + k * lo.1);
+                let above = hi.0 * d - n * hi.1;
+                let j = ((below - 1) / above).min((max - lo.1) / hi.1);
+                lo = (lo.0 + j * hi.0, lo.1 + j * hi.1);
+                if k == 0 && j == 0 {
+                    break (lo, hi);
+                }
+            }
+        };
+        let shift =
+            |(num, den): (i128, i128)| Self::from_i128(num + floor * den, den).expect(OVERFLOW);
+        (shift(lo), shift(hi))
+    }
+
+    /// The terms of the continued fraction, the first being the floor and
+    /// the others positive, e.g. `[3, 7, 16]` for `355/113` and `[-1, 2]`
+    /// for `-1/2`. The last term is above 1 unless the value is 1 itself.
+    pub fn to_continued_fraction(self) -> Vec<i64> {
+        self.continued_fraction().collect()
+    }
+
+    // the terms, computed lazily
+    fn continued_fraction(self) -> impl Iterator<Item = i64> {
+        let (mut num, mut den) = (self.0 as i128, self.1.get() as i128);
+        std::iter::from_fn(move || {
+            if den == 0 {
+                return None;
+            }
+            let term = num.div_euclid(den);
+            (num, den) = (den, num.rem_euclid(den));
+            Some(term as i64)
+        })
+    }
+
+    /// The value of the continued fraction `a0 + 1/(a1 + 1/(a2 + ...))`,
+    /// the inverse of [`to_continued_fraction`](Self::to_continued_fraction);
+    /// an empty slice is 0. Fails with [`Error::DivisionByZero`] if a
+    /// denominator along the way is zero, which nonpositive terms after the
+    /// first can cause, and with [`Error::Overflow`] if it does not fit.
+    pub fn from_continued_fraction(terms: &[i64]) -> Result<Self> {
+        if terms.is_empty() {
+            return Ok(Self::ZERO);
+        }
+        let overflow = || Error::Overflow { span: None };
+        // the numerator and denominator of the last two convergents
+        let (mut prev, mut cur) = ((0i128, 1i128), (1i128, 0i128));
+        for &a in terms {
+            let next = |p: i128, c: i128| (a as i128).checked_mul(c)?.checked_add(p);
+            let num = next(prev.0, cur.0).ok_or_else(overflow)?;
+            let den = next(prev.1, cur.1).ok_or_else(overflow)?;
+            (prev, cur) = (cur, (num, den));
+        }
+        if cur.1 == 0 {
+            return Err(Error::DivisionByZero);
+        }
+        Self::from_i128(cur.0, cur.1).ok_or_else(overflow)
+    }
+
+    /// The convergents, the successively closer fractions made from the
+    /// first terms of the continued fraction, e.g. `3`, `22/7`, `355/113`
+    /// for `355/113`. For a positive value each is closer than any fraction
+    /// with a smaller denominator, and the last is the value itself.
+    pub fn convergents(self) -> impl Iterator<Item = Rational> {
+        let (mut prev, mut cur) = ((0i128, 1i128), (1i128, 0i128));
+        self.continued_fraction().map(move |a| {
+            let a = a as i128;
+            (prev, cur) = (cur, (a * cur.0 + prev.0, a * cur.1 + prev.1));
+            // convergents are coprime and no larger than the value's parts
+            Ratio(cur.0 as i64, NonZeroI64::new(cur.1 as i64).unwrap())
+        })
+    }
+
+    /// The value as a sum of distinct unit fractions, largest first, using
+    /// the [greedy](EgyptianMethod::Greedy) method, e.g. `[1/2, 1/3]` for
+    /// `5/6`. See [`to_egyptian_with`](Self::to_egyptian_with).
+    pub fn to_egyptian(self) -> Option<Vec<Rational>> {
+        self.to_egyptian_with(EgyptianMethod::Greedy)
+    }
+
+    /// The value as a sum of distinct unit fractions, largest first, or
+    /// `None` unless it is a positive proper fraction or if a denominator
+    /// does not fit.
+    pub fn to_egyptian_with(self, method: EgyptianMethod) -> Option<Vec<Rational>> {
+        if self.0 <= 0 || !self.is_proper() {
+            return None;
+        }
+        let mut terms = Vec::new();
+        let mut rest = self;
+        while !rest.is_zero() {
+            let (num, den) = (rest.0 as i128, rest.1.get() as i128);
+            let unit = match method {
+                // the smallest c with 1/c <= rest
+                EgyptianMethod::Greedy => (den + num - 1) / num,
+                // rest - p/q is 1/(den*q) for the neighbour p/q below it
+                EgyptianMethod::Farey => den * rest.farey_neighbors(den as u64).0.1.get() as i128,
+            };
+            let unit = Self::from_i128(1, unit)?;
+            terms.push(unit);
+            rest = rest.checked_sub(unit).ok()?;
+        }
+        if method == EgyptianMethod::Farey {
+            terms.sort_by(|a, b| b.cmp(a));
+        }
+        Some(terms)
+    }
+
+    /// Every positive rational exactly once, in the order of the Calkin–Wilf
+    /// sequence: `1, 1/2, 2, 1/3, 3/2, 2/3, 3, 1/4, ...`. The `i`th one is
+    /// [`from_index(i)`](Self::from_index).
+    pub fn enumerate() -> impl Iterator<Item = Rational> {
+        // each term is 1 / (2 * floor(x) - x + 1) of the one before
+        std::iter::successors(Some(Self::ONE), |&x| {
+            let (num, den) = (x.0 as i128, x.1.get() as i128);
+            let next_den = (2 * x.floor() as i128 + 1) * den - num;
+            Self::from_i128(den, next_den)
+        })
+    }
+
+    /// The position of a positive value in the Calkin–Wilf sequence of
+    /// [`enumerate`](Self::enumerate), counting from 0 for `1`, or `None`
+    /// for other values and for positions beyond `u64`.
+    pub fn index_of(self) -> Option<u64> {
+        if self.0 <= 0 {
+            return None;
+        }
+        // walk up the Calkin–Wilf tree, where a/b has the children a/(a+b)
+        // and (a+b)/b; the path down from the root 1 are the binary digits
+        // of the position plus one after the leading 1, a right step being 1
+        let (mut a, mut b) = (self.0 as u64, self.1.get() as u64);
+        let (mut path, mut len) = (0u128, 0u32);
+        while a != b {
+            // a run of steps in the same direction at once
+            let (steps, right) = if a > b {
+                let steps = if b == 1 { a - 1 } else { a / b };
+                a -= steps * b;
+                (steps, true)
+            } else {
+                let steps = if a == 1 { b - 1 } else { b / a };
+                b -= steps * a;
+                (steps, false)
+            };
+            if steps > 64 || len + steps as u32 > 64 {
+                return None;
+            }
+            if right {
+                path |= ((1 << steps) - 1) << len;
+            }
+            len += steps as u32;
+        }
+        ((path | 1 << len) - 1).try_into().ok()
+    }
+
+    /// The value at `index` in the Calkin–Wilf sequence of
+    /// [`enumerate`](Self::enumerate), the inverse of
+    /// [`index_of`](Self::index_of).
+    pub fn from_index(index: u64) -> Rational {
+        let path = index as u128 + 1;
+        let (mut a, mut b) = (1i64, 1i64);
+        for bit in (0..path.ilog2()).rev() {
+            if path >> bit & 1 == 1 {
+                a += b;
+            } else {
+                b += a;
+            }
+        }
+        Ratio(a, NonZeroI64::new(b).unwrap())
+    }
+
+    /// Formats the value scaled to percent, e.g. `1/4` as `25%`.
+    pub fn to_percent_string(self) -> String {
+        self.to_scaled_string(100, "%")
+    }
+
+    /// Formats the value scaled to per-mille, e.g. `1/4` as `250‰`.
+    pub fn to_permille_string(self) -> String {
+        self.to_scaled_string(1000, "‰")
+    }
+
+    /// Formats the value scaled to basis points, e.g. `1/4` as `2500bps`.
+    pub fn to_basis_points_string(self) -> String {
+        self.to_scaled_string(10000, "bps")
+    }
+
+    /// Formats the value as odds, e.g. `3/2` as `3:2` and `2` as `2:1`.
+    pub fn to_odds_string(self) -> String {
+        format!("{}:{}", self.0, self.1)
+    }
+
+    /// The probability for odds in favour, e.g. `3:2` gives `3/5`. Fails with
+    /// [`Error::DivisionByZero`] for odds of `-1`.
+    pub fn odds_to_probability(self) -> Result<Self> {
+        self.checked_div(self.checked_add(1.into())?)
+    }
+
+    /// The odds in favour for a probability, e.g. `3/5` gives `3:2`. Fails
+    /// with [`Error::DivisionByZero`] for a certainty.
+    pub fn probability_to_odds(self) -> Result<Self> {
+        self.checked_div(Self::from(1).checked_sub(self)?)
+    }
+
+    /// The decimal odds for a probability, the total return on a stake of
+    /// one, e.g. `2/5` gives `5/2`. Fails with [`Error::DivisionByZero`] for
+    /// an impossible outcome.
+    pub fn probability_to_decimal_odds(self) -> Result<Self> {
+        Self::from(1).checked_div(self)
+    }
+
+    /// The probability for decimal odds, e.g. `5/2` gives `2/5`.
+    pub fn decimal_odds_to_probability(self) -> Result<Self> {
+        Self::from(1).checked_div(self)
+    }
+
+    // scaled in i128, where any i64 value times the scale fits
+    fn to_scaled_string(self, scale: i128, suffix: &str) -> String {
+        format!(
+            "{}{suffix}",
+            Rational128::from(self) * Rational128::from(scale)
+        )
+    }
+
+    /// Whether `|self - other| <= tol`, computed exactly.
+    pub fn approx_eq(&self, other: Self, tol: Rational) -> bool {
+        if tol.0 < 0 {
+            return false;
+        }
+        let (num, den) = self.wide_sum(other, -1);
+        mul_wide(num.unsigned_abs(), tol.1.get() as u128) <= mul_wide(tol.0 as u128, den as u128)
+    }
+
+    // exact `self + sign * rhs` over the least common denominator, which
+    // always fits in i128
+    fn wide_sum(self, rhs: Self, sign: i128) -> (i128, i128) {
+        let (a_den, b_den) = (self.1.get() as i128, rhs.1.get() as i128);
+        let g = gcd128(a_den as u128, b_den as u128) as i128;
+        let num = self.0 as i128 * (b_den / g) + sign * rhs.0 as i128 * (a_den / g);
+        (num, a_den / g * b_den)
+    }
+
+    fn wide_mul(self, rhs: Self) -> (i128, i128) {
+        (
+            self.0 as i128 * rhs.0 as i128,
+            self.1.get() as i128 * rhs.1.get() as i128,
+        )
+    }
+
+    // num/den (den > 0) if representable; values beyond the i64 range clamp
+    // to its extremes and anything else loses low bits until it fits
+    fn saturate(num: i128, den: i128) -> Self {
+        if let Some(v) = Self::from_i128(num, den) {
+            return v;
+        }
+        let (max, min) = (Self(i64::MAX, NZ_ONE), Self(i64::MIN, NZ_ONE));
+        let (quot, rem) = (num / den, num % den);
+        if quot > i64::MAX as i128 || (quot == i64::MAX as i128 && rem > 0) {
+            return max;
+        }
+        if quot < i64::MIN as i128 || (quot == i64::MIN as i128 && rem < 0) {
+            return min;
+        }
+
+        let g = gcd128(num.unsigned_abs(), den as u128) as i128;
+        let (num, den) = (num / g, den / g);
+        let bits = |v: u128| 128 - v.leading_zeros();
+        let shift = bits(num.unsigned_abs()).max(bits(den as u128)) - 63;
+        let scaled = (num + (1 << (shift - 1))) >> shift;
+        Self::from_i128(scaled, (den >> shift).max(1)).unwrap_or(if num < 0 { min } else { max })
+    }
+
+    /// `self + rhs`, clamping to the nearest representable value instead of
+    /// overflowing.
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        let (num, den) = self.wide_sum(rhs, 1);
+        Self::saturate(num, den)
+    }
+
+    /// `self - rhs`, clamping to the nearest representable value instead of
+    /// overflowing.
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        let (num, den) = self.wide_sum(rhs, -1);
+        Self::saturate(num, den)
+    }
+
+    /// `self * rhs`, clamping to the nearest representable value instead of
+    /// overflowing.
+    pub fn saturating_mul(self, rhs: Self) -> Self {
+        let (num, den) = self.wide_mul(rhs);
+        Self::saturate(num, den)
+    }
+
+    /// `self / rhs`, clamping to the nearest representable value instead of
+    /// overflowing. Panics if `rhs` is zero.
+    pub fn saturating_div(self, rhs: Self) -> Self {
+        if rhs.0 == 0 {
+            panic!("cannot divide by zero");
+        }
+        let num = self.0 as i128 * rhs.1.get() as i128;
+        let den = self.1.get() as i128 * rhs.0 as i128;
+        let (num, den) = if den < 0 { (-num, -den) } else { (num, den) };
+        Self::saturate(num, den)
+    }
+
+    /// The integer `q` for which `self - rhs * q` lies in `[0, |rhs|)`, see
+    /// [`rem_euclid`](Self::rem_euclid).
+    pub fn div_euclid(self, rhs: Self) -> Self {
+        let (num, den, _) = self.wide_euclid(rhs);
+        Self::from(i64::try_from(num.div_euclid(den)).expect("quotient overflows i64"))
+    }
+
+    /// The remainder of Euclidean division, always in `[0, |rhs|)` unlike a
+    /// truncating remainder, e.g. for wrapping angles or phases.
+    pub fn rem_euclid(self, rhs: Self) -> Self {
+        let (num, den, rem_den) = self.wide_euclid(rhs);
+        Self::from_i128(num.rem_euclid(den), rem_den).expect("remainder overflows Rational")
+    }
+
+    // self / rhs as a fraction num / den, with the remainder of num / den
+    // scaled by 1 / rem_den to give the remainder of self / rhs
+    fn wide_euclid(self, rhs: Self) -> (i128, i128, i128) {
+        if rhs.0 == 0 {
+            panic!("cannot divide by zero");
+        }
+        let num = self.0 as i128 * rhs.1.get() as i128;
+        let den = self.1.get() as i128 * rhs.0 as i128;
+        (num, den, self.1.get() as i128 * rhs.1.get() as i128)
+    }
+
+    /// The exact arithmetic mean of `iter`, or `None` if it is empty or the
+    /// mean is not representable.
+    pub fn mean<I: IntoIterator<Item = Rational>>(iter: I) -> Option<Rational> {
+        let mut acc = Accumulator::default();
+        let mut count = 0;
+        for v in iter {
+            acc.add(v)?;
+            count += 1;
+        }
+        if count == 0 {
+            return None;
+        }
+        acc.den = acc.den.checked_mul(count)?;
+        acc.to_rational()
+    }
+
+    /// The value as an integer, failing with [`Error::NotAnInteger`] unless
+    /// the denominator is 1.
+    pub fn to_integer(&self) -> Result<i64> {
+        if self.1 == NZ_ONE {
+            Ok(self.0)
+        } else {
+            Err(Error::NotAnInteger(*self))
+        }
+    }
+
+    /// The nearest `f64`, rounding ties to even. The result is correctly
+    /// rounded even when the numerator or denominator has more than the 53
+    /// bits of the mantissa, where the parts themselves would be rounded.
+    pub fn to_f64(self) -> f64 {
+        let (m, exp) = self.scaled_quotient(f64::MANTISSA_DIGITS);
+        m as f64 * f64::from_bits(((exp + 1023) as u64) << 52)
+    }
+
+    /// The nearest `f32`, rounding ties to even like [`to_f64`](Self::to_f64).
+    pub fn to_f32(self) -> f32 {
+        let (m, exp) = self.scaled_quotient(f32::MANTISSA_DIGITS);
+        m as f32 * f32::from_bits(((exp + 127) as u32) << 23)
+    }
+
+    // the value as m * 2^exp, with at least `digits + 1` bits of the quotient
+    // in m followed by a sticky bit that is set if any remainder was cut
+    // off, so that rounding m to `digits` bits rounds the exact value
+    fn scaled_quotient(self, digits: u32) -> (i128, i32) {
+        if self.0 == 0 {
+            return (0, 0);
+        }
+        let (num, den) = (self.0.unsigned_abs() as u128, self.1.get() as u128);
+        let bits = |v: u128| (128 - v.leading_zeros()) as i32;
+        // puts the quotient in [2^digits, 2^(digits + 2)), which for i64
+        // parts never shifts past 117 bits
+        let shift = digits as i32 + 1 - (bits(num) - bits(den));
+        let (num, den) = if shift >= 0 {
+            (num << shift, den)
+        } else {
+            (num, den << -shift)
+        };
+        let m = (num / den) << 1 | (num % den != 0) as u128;
+        let m = if self.0 < 0 { -(m as i128) } else { m as i128 };
+        (m, -shift - 1)
+    }
+
+    /// A JSON object for web frontends: `{"num", "den", "exact": true}` when
+    /// both parts are safe integers in JavaScript, otherwise a lossy
+    /// `{"value", "exact": false}`.
+    #[cfg(feature = "serde_json")]
+    pub fn to_json_value(&self) -> serde_json::Value {
+        const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+        let den = self.1.get();
+        if self.0.unsigned_abs() <= MAX_SAFE_INTEGER && den as u64 <= MAX_SAFE_INTEGER {
+            serde_json::json!({ "num": self.0, "den": den, "exact": true })
+        } else {
+            serde_json::json!({ "value": self.to_f64(), "exact": false })
+        }
+    }
+
+    /// The duration in exact seconds, e.g. `3/2` for 1.5s. Panics if they
+    /// are not representable, which can only happen for durations of about
+    /// 292 years or more.
+    pub fn from_duration(d: Duration) -> Self {
+        let nanos = d.as_secs() as i128 * NANOS_PER_SEC + d.subsec_nanos() as i128;
+        Self::from_i128(nanos, NANOS_PER_SEC).expect("duration overflows Rational")
+    }
+
+    /// The value as a [`Duration`] in seconds, rounded to the nearest
+    /// nanosecond with ties to even, e.g. a frame at `30000/1001` fps,
+    /// `1001/30000`, gives 33366667ns. Fails with [`Error::Inexact`] for
+    /// negative values and with [`Error::Overflow`] beyond
+    /// [`Duration::MAX`]. See [`try_to_duration`](Self::try_to_duration) to
+    /// refuse rounding instead.
+    pub fn to_duration(&self) -> Result<Duration> {
+        let (negative, num, den) = self.sign_magnitude();
+        if negative {
+            return Err(Error::Inexact(*self));
+        }
+        let nanos = round_magnitude(
+            false,
+            num * NANOS_PER_SEC as u128,
+            den,
+            RoundingMode::HalfEven,
+        );
+        let secs = u64::try_from(nanos / NANOS_PER_SEC as u128)
+            .map_err(|_| Error::Overflow { span: None })?;
+        Ok(Duration::new(secs, (nanos % NANOS_PER_SEC as u128) as u32))
+    }
+
+    /// The value as a [`Duration`] in seconds. Fails with
+    /// [`Error::Inexact`] for negative values and values that are not a
+    /// whole number of nanoseconds.
+    pub fn try_to_duration(&self) -> Result<Duration> {
+        let den = self.1.get() as i128;
+        if self.0 < 0 || NANOS_PER_SEC % den != 0 {
+            return Err(Error::Inexact(*self));
+        }
+        let nanos = self.0 as i128 * (NANOS_PER_SEC / den);
+        let secs =
+            u64::try_from(nanos / NANOS_PER_SEC).map_err(|_| Error::Overflow { span: None })?;
+        Ok(Duration::new(secs, (nanos % NANOS_PER_SEC) as u32))
+    }
+
+    /// `self` raised to `exp`, a negative one raising the reciprocal, e.g.
+    /// `(2/3)^-2` is `9/4`.
+    ///
+    /// Panics if the result does not fit or for `0^-n`, see
+    /// [`checked_pow`](Self::checked_pow).
+    pub fn pow(self, exp: i32) -> Self {
+        match self.checked_pow(exp) {
+            Ok(v) => v,
+            Err(Error::ZeroToNegativePower) => panic!("cannot raise zero to a negative power"),
+            Err(_) => panic!("{OVERFLOW}"),
+        }
+    }
+
+    /// `self` raised to `exp`, failing with [`Error::Overflow`] if either
+    /// part overflows and with [`Error::ZeroToNegativePower`] for `0^-n`.
+    pub fn checked_pow(self, exp: i32) -> Result<Self> {
+        if self.0 == 0 && exp < 0 {
+            return Err(Error::ZeroToNegativePower);
+        }
+        // powers in i128 so that e.g. (-1/2)^-63 still reaches i64::MIN
+        let pow = |v: i64| {
+            (v as i128)
+                .checked_pow(exp.unsigned_abs())
+                .ok_or(Error::Overflow { span: None })
+        };
+        let (num, den) = (pow(self.0)?, pow(self.1.get())?);
+        let (num, den) = if exp < 0 { (den, num) } else { (num, den) };
+        Self::from_i128(num, den).ok_or(Error::Overflow { span: None })
+    }
+}
+
+// what `eval` computes with, so that it can run on the i64 `Rational` and on
+// wider types alike
+trait Number: Clone + Default {
+    fn from_rational(v: Rational) -> Self;
+    // the value for functions and diagnostics, which only know `Rational`
+    fn to_rational(&self) -> Result<Rational>;
+    fn try_add(&self, rhs: &Self) -> Result<Self>;
+    fn try_sub(&self, rhs: &Self) -> Result<Self>;
+    fn try_mul(&self, rhs: &Self) -> Result<Self>;
+    fn try_div(&self, rhs: &Self) -> Result<Self>;
+    // the value of the literal INT.FRAC(PERIOD), from its digits
+    fn from_literal(int: &[u8], frac: &[u8], period: &[u8]) -> Result<Self>;
+}
+
+impl Number for Rational {
+    fn from_rational(v: Rational) -> Self {
+        v
+    }
+
+    fn to_rational(&self) -> Result<Rational> {
+        Ok(*self)
+    }
+
+    fn try_add(&self, rhs: &Self) -> Result<Self> {
+        self.checked_add(*rhs)
+    }
+
+    fn try_sub(&self, rhs: &Self) -> Result<Self> {
+        self.checked_sub(*rhs)
+    }
+
+    fn try_mul(&self, rhs: &Self) -> Result<Self> {
+        self.checked_mul(*rhs)
+    }
+
+    fn try_div(&self, rhs: &Self) -> Result<Self> {
+        self.checked_div(*rhs)
+    }
+
+    fn from_literal(int: &[u8], frac: &[u8], period: &[u8]) -> Result<Self> {
+        decimal_value(false, int, frac, period)
+            .and_then(|v| Rational::try_from(v).ok())
+            .ok_or(Error::Overflow { span: None })
+    }
+}
+
+// evaluates `expr`, which starts at character `offset` of the whole input
+// so that error positions and spans refer to the whole input, recording
+// every value of a subexpression in `trace`
+fn eval<N: Number>(expr: &str, offset: usize, ctx: &Context, trace: &mut Trace<N>) -> Result<N> {
+    let mut parts = Vec::new();
+    let mut ops = Vec::new();
+
+    let mut cur: Option<(N, Range<usize>)> = None;
+    // where the unary minuses in front of the next operand start, and
+    // whether they negate it
+    let mut sign: Option<(usize, bool)> = None;
+    let mut chars = expr.char_indices().enumerate();
+    while let Some((index, (pos, c))) = chars.next() {
+        let index = offset + index;
+        if let Some(&(suffix, scale)) = SUFFIXES.iter().find(|(s, _)| expr[pos..].starts_with(s)) {
+            let Some((v, span)) = cur.as_mut() else {
+                return Err(Error::InvalidSyntax(index));
+            };
+            let len = suffix.chars().count();
+            span.end = index + len;
+            *v = v
+                .try_div(&N::from_rational(scale.into()))
+                .map_err(|e| e.with_span(span))?;
+            for _ in 1..len {
+                chars.next();
+            }
+            continue;
+        }
+
+        match c {
+            '0'..='9' | '.' => {
+                if cur.is_some() {
+                    return Err(Error::InvalidSyntax(index));
+                }
+                let (len, [int, frac, period]) = lex_number(&expr[pos..]);
+                if int.is_empty() && frac.is_empty() {
+                    return Err(Error::InvalidSyntax(index));
+                }
+                let span = index..index + len;
+                let value =
+                    N::from_literal(&int, &frac, &period).map_err(|e| e.with_span(&span))?;
+                for _ in 1..len {
+                    chars.next();
+                }
+                cur = Some((value, span));
+            }
+            op @ ('+' | '-' | '*' | '/') => match cur.take() {
+                Some(v) => {
+                    let v = signed(v, sign.take())?;
+                    trace.record(&v);
+                    parts.push(v);
+                    ops.push(op.into());
+                }
+                // a sign at the start or right after another operator
+                None if op == '-' => {
+                    let (_, negative) = sign.get_or_insert((index, false));
+                    *negative = !*negative;
+                }
+                None => return Err(Error::InvalidSyntax(index)),
+            },
+            'a'..='z' | 'A'..='Z' | '(' => {
+                if cur.is_some() {
+                    return Err(Error::InvalidSyntax(index));
+                }
+                // a variable, or a function name if followed by the
+                // opening parenthesis
+                let len = expr[pos..]
+                    .find(|c: char| !c.is_ascii_alphanumeric())
+                    .unwrap_or(expr.len() - pos);
+                let name = &expr[pos..pos + len];
+                for _ in 1..len {
+                    chars.next();
+                }
+                if !expr[pos + len..].starts_with('(') {
+                    let span = index..index + len;
+                    let value = ctx
+                        .get(name)
+                        .ok_or_else(|| Error::UnknownVariable(name.to_string()))?;
+                    cur = Some((N::from_rational(value), span));
+                    continue;
+                }
+                let (open, open_pos) = (index + len, pos + len);
+                if len > 0 {
+                    chars.next();
+                }
+
+                let mut depth = 1;
+                let (close, close_pos) = loop {
+                    let Some((i, (p, c))) = chars.next() else {
+                        return Err(Error::NeedMoreInput);
+                    };
+                    match c {
+                        '(' => depth += 1,
+                        ')' if depth == 1 => break (offset + i, p),
+                        ')' => depth -= 1,
+                        _ => (),
+                    }
+                };
+                // the group is closed, so an incomplete one is just invalid
+                let arg = eval(&expr[open_pos + 1..close_pos], open + 1, ctx, trace).map_err(
+                    |e| match e {
+                        Error::NeedMoreInput => Error::InvalidExpr,
+                        e => e,
+                    },
+                )?;
+
+                let span = index..close + 1;
+                // functions are defined on i64 values only
+                let value = if name.is_empty() {
+                    Ok(arg)
+                } else if let Some((_, f)) = FUNCTIONS.iter().find(|(n, _)| *n == name) {
+                    arg.to_rational().and_then(f).map(N::from_rational)
+                } else if let Some(f) = ctx.function(name) {
+                    arg.to_rational().and_then(|v| f(v)).map(N::from_rational)
+                } else {
+                    return Err(Error::UnknownFunction(name.to_string()));
+                };
+                let value = value.map_err(|e| e.with_span(&span))?;
+                cur = Some((value, span));
+            }
+            '$' => {
+                if cur.is_some() {
+                    return Err(Error::InvalidSyntax(index));
+                }
+                // `$NAME` also allows digits and underscores in the name
+                let rest = &expr[pos + 1..];
+                let len = rest
+                    .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .unwrap_or(rest.len());
+                if len == 0 {
+                    return Err(Error::InvalidSyntax(index + 1));
+                }
+                let name = &rest[..len];
+                for _ in 0..len {
+                    chars.next();
+                }
+                let value = ctx
+                    .get(name)
+                    .ok_or_else(|| Error::UnknownVariable(name.to_string()))?;
+                cur = Some((N::from_rational(value), index..index + len + 1));
+            }
+            ' ' => (),
+            _ => return Err(Error::InvalidSyntax(index)),
+        }
+    }
+
+    // eval
+    let Some(last) = cur else {
+        if ops.is_empty() && sign.is_none() {
+            return Err(Error::InvalidExpr);
+        }
+        return Err(Error::NeedMoreInput);
+    };
+    let last = signed(last, sign)?;
+    trace.record(&last);
+    parts.push(last);
+    // eprintln!("parts: {parts:?}");
+    // eprintln!("ops: {ops:?}");
+
+    for cur_ops in OP_PRECEDENCE {
+        let mut index = 0;
+        while index < ops.len() {
+            if cur_ops.contains(&ops[index]) {
+                let op = ops.remove(index);
+                let (a, a_span) = parts.remove(index);
+                let b = &mut parts[index];
+                b.1.start = a_span.start;
+                b.0 = op.compute(&a, &b.0).map_err(|e| e.with_span(&b.1))?;
+                trace.record(b);
+            } else {
+                index += 1;
+            }
+        }
+    }
+
+    Ok(parts.swap_remove(0).0)
+}
+
+// an operand negated by the unary minuses in `sign`, its span then starting
+// at the first of them
+fn signed<N: Number>(
+    (v, span): (N, Range<usize>),
+    sign: Option<(usize, bool)>,
+) -> Result<(N, Range<usize>)> {
+    let Some((start, negative)) = sign else {
+        return Ok((v, span));
+    };
+    let span = start..span.end;
+    if !negative {
+        return Ok((v, span));
+    }
+    let v = N::default().try_sub(&v).map_err(|e| e.with_span(&span))?;
+    Ok((v, span))
+}
+
+// the longest number literal `INT[.FRAC][(PERIOD)]` at the start of `s`, as
+// its length and the digits of its parts
+fn lex_number(s: &str) -> (usize, [Vec<u8>; 3]) {
+    let digits = |s: &str| {
+        s.bytes()
+            .take_while(u8::is_ascii_digit)
+            .map(|b| b - b'0')
+            .collect::<Vec<_>>()
+    };
+    let int = digits(s);
+    let mut len = int.len();
+    let (mut frac, mut period) = (Vec::new(), Vec::new());
+    if let Some(rest) = s[len..].strip_prefix('.') {
+        frac = digits(rest);
+        len += 1 + frac.len();
+        // only a literal with a point has a period, so `2(3)` is not one
+        if let Some(rest) = s[len..].strip_prefix('(') {
+            let digits = digits(rest);
+            if !digits.is_empty() && rest[digits.len()..].starts_with(')') {
+                len += digits.len() + 2;
+                period = digits;
+            }
+        }
+    }
+    (len, [int, frac, period])
+}
+
+/// Sorts `values` in ascending order using [`Rational::total_cmp`].
+pub fn sort_rationals(values: &mut [Rational]) {
+    values.sort_by(Rational::total_cmp);
+}
+
+/// The smallest and largest of `values`, or `None` if it is empty.
+pub fn min_max(values: &[Rational]) -> Option<(Rational, Rational)> {
+    let (&first, rest) = values.split_first()?;
+    Some(rest.iter().fold((first, first), |(min, max), v| {
+        (
+            if v.total_cmp(&min).is_lt() { *v } else { min },
+            if v.total_cmp(&max).is_gt() { *v } else { max },
+        )
+    }))
+}
+
+/// The smallest of `values`, or `None` if it is empty. For two values, or to
+/// limit one to a range, use [`Ord::min`], [`Ord::max`] and [`Ord::clamp`].
+pub fn min_of(values: &[Rational]) -> Option<Rational> {
+    values.iter().copied().min()
+}
+
+/// The largest of `values`, or `None` if it is empty.
+pub fn max_of(values: &[Rational]) -> Option<Rational> {
+    values.iter().copied().max()
+}
+
+/// The relative difference `|a - b| / max(|a|, |b|)`, which is 0 when both
+/// are zero. Fails with [`Error::Overflow`] if a step does not fit.
+pub fn relative_diff(a: Rational, b: Rational) -> Result<Rational> {
+    let largest = checked_abs(a)?.max(checked_abs(b)?);
+    if largest.is_zero() {
+        return Ok(Rational::default());
+    }
+    checked_abs(a.checked_sub(b)?)?.checked_div(largest)
+}
+
+// `|v|`, failing for a numerator of `i64::MIN`
+fn checked_abs(v: Rational) -> Result<Rational> {
+    if v.is_negative() {
+        Rational::ZERO.checked_sub(v)
+    } else {
+        Ok(v)
+    }
+}
+
+/// The point at `t` of the way from `a` to `b`, `a + (b - a) * t`, so `a`
+/// for 0 and `b` for 1. Values of `t` outside `0..=1` extrapolate. Fails
+/// with [`Error::Overflow`] if a step does not fit.
+pub fn lerp(a: Rational, b: Rational, t: Rational) -> Result<Rational> {
+    a.checked_add(b.checked_sub(a)?.checked_mul(t)?)
+}
+
+/// The change from `old` to `new` in percent, relative to `|old|` so a
+/// positive result always means an increase. Fails with
+/// [`Error::DivisionByZero`] when `old` is zero and with [`Error::Overflow`]
+/// if a step does not fit.
+pub fn percent_change(old: Rational, new: Rational) -> Result<Rational> {
+    new.checked_sub(old)?
+        .checked_div(checked_abs(old)?)?
+        .checked_mul(100.into())
+}
+
+impl FromStr for Rational {
+    type Err = Error;
+
+    /// Parses a decimal, a fraction of decimals or a mixed number exactly,
+    /// e.g. `-0.625`, `5/8`, `1.5/2`, `-3-1/2` or `-3 1/2`, with an optional `%`, `‰`
+    /// or `bps` suffix. A decimal can end in a repeating part in
+    /// parentheses, e.g. `0.1(6)` for `1/6`. Odds like `3:2` parse as their ratio `3/2`, see
+    /// [`odds_to_probability`](Rational::odds_to_probability). Syntax errors
+    /// carry the character position like [`Rational::run_expr`].
+    ///
+    /// Everything [`Display`](std::fmt::Display),
+    /// [`to_radix_fraction_string(10)`](Rational::to_radix_fraction_string),
+    /// [`display_mixed`](Rational::display_mixed),
+    /// [`to_odds_string`](Rational::to_odds_string),
+    /// [`to_repeating_decimal`](Rational::to_repeating_decimal) and
+    /// [`to_percent_string`](Rational::to_percent_string) and its siblings
+    /// produce parses back to the same value.
+    fn from_str(s: &str) -> Result<Self> {
+        if let Some((a, b)) = s.split_once(':') {
+            let b_start = a.chars().count() + 1;
+            let value = parse_decimal(a, 0)?.checked_div(parse_decimal(b, b_start)?)?;
+            return Rational::try_from(value);
+        }
+        let (body, scale) = SUFFIXES
+            .iter()
+            .find_map(|&(suffix, scale)| s.strip_suffix(suffix).map(|body| (body, scale)))
+            .unwrap_or((s, 1));
+        // a sign after the whole part of a mixed number, before any `/`
+        let sign = body
+            .char_indices()
+            .skip(1)
+            .take_while(|&(_, c)| c != '/')
+            .find(|&(_, c)| c == '+' || c == '-');
+        let value = match (body.split_once(' '), sign) {
+            // `INT FRAC` as written by `display_mixed`, the sign of the whole
+            // part covering both
+            (Some((int, frac)), _) => {
+                let frac_start = int.chars().count() + 1;
+                if frac.starts_with(['+', '-']) {
+                    return Err(Error::InvalidSyntax(frac_start));
+                }
+                let frac = parse_fraction(frac, frac_start)?;
+                let int_value = parse_decimal(int, 0)?;
+                if int.starts_with('-') {
+                    int_value.checked_sub(frac)?
+                } else {
+                    int_value.checked_add(frac)?
+                }
+            }
+            (None, Some((i, _))) => {
+                let frac = parse_fraction(&body[i..], body[..i].chars().count())?;
+                parse_decimal(&body[..i], 0)?.checked_add(frac)?
+            }
+            (None, None) => parse_fraction(body, 0)?,
+        };
+        // the parts are parsed in i128, so that a scaled value like
+        // `922337203685477580700%` fits until it is scaled back
+        Rational::try_from(value.checked_div(Rational128::from(scale as i128))?)
+    }
+}
+
+// `DECIMAL[/DECIMAL]`, starting at character `offset` of the whole input
+fn parse_fraction(s: &str, offset: usize) -> Result<Rational128> {
+    match s.split_once('/') {
+        Some((num, den)) => {
+            let den_start = offset + num.chars().count() + 1;
+            parse_decimal(num, offset)?.checked_div(parse_decimal(den, den_start)?)
+        }
+        None => parse_decimal(s, offset),
+    }
+}
+
+// `[+-]INT[.FRAC][(PERIOD)]`, the period repeating forever, starting at
+// character `offset` of the whole input
+fn parse_decimal(s: &str, offset: usize) -> Result<Rational128> {
+    let digits = s.strip_prefix(['+', '-']).unwrap_or(s);
+    let start = offset + s.len() - digits.len();
+    let len = digits.chars().count();
+    let (mut int, mut frac, mut period) = (Vec::new(), Vec::new(), None::<Vec<u8>>);
+    let mut point = false;
+    for (i, c) in digits.chars().enumerate() {
+        match c {
+            '0'..='9' => {
+                let digit = c as u8 - b'0';
+                match (&mut period, point) {
+                    (Some(period), _) => period.push(digit),
+                    (None, true) => frac.push(digit),
+                    (None, false) => int.push(digit),
+                }
+            }
+            '.' if !point => point = true,
+            '(' if point && period.is_none() => period = Some(Vec::new()),
+            ')' if period.as_ref().is_some_and(|p| !p.is_empty()) && i + 1 == len => {}
+            _ => return Err(Error::InvalidSyntax(start + i)),
+        }
+    }
+    if int.is_empty() && frac.is_empty() {
+        return Err(Error::InvalidSyntax(start + len));
+    }
+    if period.is_some() && !digits.ends_with(')') {
+        return Err(Error::InvalidSyntax(start + len));
+    }
+    let negative = s.starts_with('-');
+    decimal_value(negative, &int, &frac, period.as_deref().unwrap_or_default())
+        .ok_or(Error::Overflow { span: None })
+}
+
+// the value of INT.FRAC(PERIOD), negated if `negative`, if it fits in i128
+fn decimal_value(negative: bool, int: &[u8], frac: &[u8], period: &[u8]) -> Option<Rational128> {
+    // trailing zeros do not change a terminating decimal, but its
+    // denominator would be needlessly large
+    let frac = match period {
+        [] => &frac[..frac.iter().rposition(|&d| d != 0).map_or(0, |i| i + 1)],
+        _ => frac,
+    };
+    let sign = if negative { -1 } else { 1 };
+    let number = |digits: &[u8]| {
+        digits
+            .iter()
+            .try_fold(0i128, |n, &d| n.checked_mul(10)?.checked_add(d as i128))
+    };
+    let exact = || {
+        let num = number(&[int, frac].concat())?;
+        let den = 10i128.checked_pow(frac.len() as u32)?;
+        if period.iter().all(|&d| d == 9) {
+            // no period, or 0.(9), which is 1
+            let carry = !period.is_empty() as i128;
+            return Rational128::new(sign * num.checked_add(carry)?, den).ok();
+        }
+        // INT.FRAC(PERIOD) is (INTFRAC * nines + PERIOD) / (den * nines) for
+        // nines = 10^len - 1 with the length of the period
+        let nines = 10i128.checked_pow(period.len() as u32)? - 1;
+        let num = num.checked_mul(nines)?.checked_add(number(period)?)?;
+        Rational128::new(sign * num, den.checked_mul(nines)?).ok()
+    };
+    exact().or_else(|| long_decimal_value(negative, int, frac, period))
+}
+
+// INT.FRAC(PERIOD), negated if `negative`, for digits too many for i128
+// intermediates. The simplest fraction within 10^-38 of the fractional part
+// is its exact value if that fits, since any two fractions with i64
+// denominators are further apart, and it is checked against enough digits to
+// rule out a false match.
+fn long_decimal_value(
+    negative: bool,
+    int: &[u8],
+    frac: &[u8],
+    period: &[u8],
+) -> Option<Rational128> {
+    const DIGITS: u32 = 38;
+    if frac.is_empty() && period.is_empty() {
+        return None;
+    }
+    let stream = || {
+        let period = period.iter().cycle();
+        frac.iter()
+            .chain(period)
+            .copied()
+            .chain(std::iter::repeat(0))
+    };
+    let lo = stream()
+        .take(DIGITS as usize)
+        .fold(0u128, |n, d| n * 10 + d as u128);
+    let scale = 10u128.pow(DIGITS);
+    let (num, den) = simplest_between(lo, scale, lo + 1, scale);
+    let fract = Rational::from_i128(num.try_into().ok()?, den.try_into().ok()?)?;
+    // two different expansions of fractions with denominators below 10^19
+    // differ within this many digits
+    let checked = frac.len() + period.len() + 20;
+    if !fract.digits().take(checked).eq(stream().take(checked)) {
+        return None;
+    }
+    let fract = Rational128::from(if negative { -fract } else { fract });
+    decimal_value(negative, int, &[], &[])?
+        .checked_add(fract)
+        .ok()
+}
+
+// the fraction with the smallest denominator in `[a/b, c/d]`, by comparing
+// continued fraction expansions
+fn simplest_between(a: u128, b: u128, c: u128, d: u128) -> (u128, u128) {
+    let int = a / b;
+    if int * b == a {
+        return (int, 1);
+    }
+    if (int + 1) * d <= c {
+        return (int + 1, 1);
+    }
+    // both share the integer part, so continue with the reciprocals of the
+    // fractional parts, which swap the bounds
+    let (num, den) = simplest_between(d, c - int * d, b, a - int * b);
+    (int * num + den, num)
+}
+
+impl From<Rational> for f64 {
+    fn from(v: Rational) -> Self {
+        v.to_f64()
+    }
+}
+
+impl From<Rational> for f32 {
+    fn from(v: Rational) -> Self {
+        v.to_f32()
+    }
+}
+
+/// Fails with [`Error::NotAnInteger`] unless the denominator is 1.
+impl TryFrom<Rational> for i64 {
+    type Error = Error;
+
+    fn try_from(value: Rational) -> Result<Self> {
+        value.to_integer()
+    }
+}
+
+/// See [`Rational::from_duration`].
+impl From<Duration> for Rational {
+    fn from(d: Duration) -> Self {
+        Self::from_duration(d)
+    }
+}
+
+/// Scales the duration exactly and rounds the result once, to the nearest
+/// nanosecond with ties to even, so e.g. multiplying by a frame count does
+/// not accumulate drift. Panics for a negative factor or if the result does
+/// not fit in a [`Duration`].
+impl Mul<Duration> for Rational {
+    type Output = Duration;
+
+    fn mul(self, rhs: Duration) -> Self::Output {
+        let (negative, num, den) = self.sign_magnitude();
+        if negative && num != 0 {
+            panic!("cannot scale a Duration by a negative value");
+        }
+        // `nanos * num / den` in u128, split so that no intermediate exceeds
+        // the result: the whole part times `num`, which is at most the
+        // result, and the remainder times `num`, which is below `den^2`
+        let nanos = rhs.as_nanos();
+        let whole = (nanos / den).checked_mul(num).expect("duration overflows");
+        // adding the parity of `whole` as one more `den` lets ties round to
+        // even for the total quotient
+        let rest = (nanos % den) * num + whole % 2 * den;
+        let nanos = whole - whole % 2 + round_magnitude(false, rest, den, RoundingMode::HalfEven);
+        let secs = u64::try_from(nanos / NANOS_PER_SEC as u128).expect("duration overflows");
+        Duration::new(secs, (nanos % NANOS_PER_SEC as u128) as u32)
+    }
+}
+
+/// Scales exactly and rounds once, like `Rational * Duration`.
+impl Mul<Rational> for Duration {
+    type Output = Duration;
+
+    fn mul(self, rhs: Rational) -> Self::Output {
+        rhs * self
+    }
+}
+
+impl Product for Rational {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::ONE, Self::mul)
+    }
+}
+
+/// Exact sum starting from zero. Intermediate sums may exceed the range of
+/// `i64`; panics if the total does not fit.
+impl Sum for Rational {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        let mut acc = Accumulator::default();
+        for v in iter {
+            acc.add(v).expect("Rational sum overflow");
+        }
+        acc.to_rational().expect("Rational sum overflow")
+    }
+}
+
+impl<'a> Sum<&'a Rational> for Rational {
+    fn sum<I: Iterator<Item = &'a Rational>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl<T: Integer> Default for Ratio<T> {
+    fn default() -> Self {
+        Self(T::ZERO, T::NZ_ONE)
+    }
+}
+
+impl<T: Integer> From<T> for Ratio<T> {
+    fn from(v: T) -> Self {
+        Self(v, T::NZ_ONE)
+    }
+}
 
-impl Rational {
-    fn normalize(&self) -> Self {
-        let gcd = gcd(self.0.unsigned_abs(), self.1.unsigned_abs()) as i64;
-        let (a, b) = (self.0 / gcd, self.1 / gcd);
-        Self(a, b)
+// values are always canonical, so equal values have equal parts and hash
+// alike; a negative denominator never reaches the fields
+impl<T: Integer> PartialEq for Ratio<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0 && self.1 == other.1
     }
+}
 
-    pub fn run_expr(expr: &str) -> Result<Self> {
-        let mut parts = Vec::new();
-        let mut ops = Vec::new();
-
-        let mut cur: Option<Rational> = None;
-        for (index, c) in expr.chars().enumerate() {
-            match c {
-                '0'..='9' => *cur.get_or_insert_default() += (c as u8 - b'0') as u64,
-                op @ ('+' | '-' | '*' | '/') => {
-                    if let Some(v) = cur.take() {
-                        parts.push(v);
-                    }
-                    let op: Op = op.into();
-                    ops.push(op);
-                }
-                ' ' => (),
-                _ => return Err(Error::InvalidSyntax(index)),
-            }
-        }
+impl<T: Integer> Eq for Ratio<T> {}
 
-        // eval
-        let Some(last) = cur else {
-            return Err(Error::InvalidExpr);
-        };
-        parts.push(last);
-        // eprintln!("parts: {parts:?}");
-        // eprintln!("ops: {ops:?}");
-
-        for cur_ops in OP_PRECEDENCE {
-            let mut index = 0;
-            while index < ops.len() {
-                if cur_ops.contains(&ops[index]) {
-                    let op = ops.remove(index);
-                    let a = parts.remove(index);
-                    let b = &mut parts[index];
-                    *b = op.compute(a, *b)?;
-                } else {
-                    index += 1;
-                }
-            }
-        }
+impl<T: Integer> Hash for Ratio<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+        self.1.hash(state);
+    }
+}
 
-        Ok(parts[0])
+impl<T: Integer> PartialOrd for Ratio<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
+}
 
-    pub fn checked_div(self, other: Self) -> Result<Self> {
-        if other.0 == 0 {
-            Err(Error::DivisionByZero)
-        } else {
-            Ok(self / other)
-        }
+/// See [`Rational::total_cmp`].
+impl<T: Integer> Ord for Ratio<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.total_cmp(other)
     }
 }
 
-impl Product for Rational {
-    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
-        iter.fold(Self(1, 1), Self::mul)
+/// Compares with the exact value of the float, so `1/10` is not equal to
+/// `0.1`, which is slightly larger. Nothing compares to NaN.
+impl PartialEq<f64> for Rational {
+    fn eq(&self, other: &f64) -> bool {
+        self.partial_cmp(other) == Some(Ordering::Equal)
     }
 }
 
-impl Default for Rational {
-    fn default() -> Self {
-        Self(0, 1)
+/// Compares with the exact value of the float, like `PartialEq<f64>`.
+impl PartialOrd<f64> for Rational {
+    fn partial_cmp(&self, other: &f64) -> Option<Ordering> {
+        if other.is_nan() {
+            return None;
+        }
+        let sign = self.0.signum().cmp(&(other.signum() as i64));
+        if sign.is_ne() || *other == 0.0 {
+            // a float zero has a sign of its own
+            return Some(if *other == 0.0 { self.0.cmp(&0) } else { sign });
+        }
+        if other.is_infinite() {
+            return Some(if *other > 0.0 {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            });
+        }
+        // |other| is mant * 2^exp
+        let (mant, exp) = float_parts(*other);
+        // compares |self.0| * 2^-exp with mant * den, where a shift that
+        // leaves u128 makes its side the larger
+        let (mut lhs, mut rhs) = (
+            self.0.unsigned_abs() as u128,
+            mant as u128 * self.1.get() as u128,
+        );
+        let (side, shift) = if exp < 0 {
+            (&mut lhs, exp.unsigned_abs())
+        } else {
+            (&mut rhs, exp as u32)
+        };
+        let magnitude = if shift >= side.leading_zeros() {
+            if exp < 0 {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            }
+        } else {
+            *side <<= shift;
+            lhs.cmp(&rhs)
+        };
+        Some(if self.0 < 0 {
+            magnitude.reverse()
+        } else {
+            magnitude
+        })
     }
 }
 
-impl Neg for Rational {
+impl<T: Integer> Neg for Ratio<T> {
     type Output = Self;
 
     fn neg(self) -> Self::Output {
-        Self(-self.0, self.1)
+        Self(self.0.checked_neg().expect(OVERFLOW), self.1)
     }
 }
 
-impl Add for Rational {
-    type Output = Self;
+impl<T: Integer> Neg for &Ratio<T> {
+    type Output = Ratio<T>;
 
-    fn add(self, rhs: Self) -> Self::Output {
-        Self(self.0 * rhs.1 + rhs.0 * self.1, self.1 * rhs.1).normalize()
+    fn neg(self) -> Self::Output {
+        -*self
     }
 }
 
-impl AddAssign for Rational {
-    fn add_assign(&mut self, rhs: Self) {
-        *self = Self(self.0 * rhs.1 + rhs.0 * self.1, self.1 * rhs.1).normalize();
+impl<T: Integer> Add for Ratio<T> {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        self.checked_add(rhs).expect(OVERFLOW)
     }
 }
 
-impl Sub for Rational {
+impl<T: Integer> Sub for Ratio<T> {
     type Output = Self;
 
     fn sub(self, rhs: Self) -> Self::Output {
-        Self(self.0 * rhs.1 - rhs.0 * self.1, self.1 * rhs.1).normalize()
+        self.checked_sub(rhs).expect(OVERFLOW)
     }
 }
-impl Mul for Rational {
+impl<T: Integer> Mul for Ratio<T> {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self::Output {
-        Self(self.0 * rhs.0, self.1 * rhs.1).normalize()
+        self.checked_mul(rhs).expect(OVERFLOW)
     }
 }
-impl Div for Rational {
+impl<T: Integer> Div for Ratio<T> {
     type Output = Self;
 
     fn div(self, rhs: Self) -> Self::Output {
-        if rhs.1 == 0 {
+        if rhs.0 == T::ZERO {
             panic!("cannot divide by zero");
         }
-        Self(self.0 * rhs.1, self.1 * rhs.0).normalize()
+        self.checked_div(rhs).expect(OVERFLOW)
     }
 }
 
-impl Debug for Rational {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let (a, b) = (self.0, self.1);
-        // let (a, b) = (self.0, self.1);
-        if b == 1 {
-            write!(f, "{a}")
-        } else if b == -1 {
-            write!(f, "{}", -a)
-        } else if a / b == 0 {
-            write!(f, "{}/{}", a % b, b)
-        } else {
-            write!(f, "{}+{}/{}", a / b, a % b, b)
-        }
+/// `a % b` is `a - b * (a / b).floor()`, which has the sign of `b` like a
+/// floored modulo, e.g. `-1/2 % 2` is `3/2`. See
+/// [`rem_euclid`](Rational::rem_euclid) for a remainder that is never
+/// negative. Panics if `rhs` is zero.
+impl Rem for Rational {
+    type Output = Self;
+
+    fn rem(self, rhs: Self) -> Self::Output {
+        let (num, den, rem_den) = self.wide_euclid(rhs);
+        let rem = num.rem_euclid(den);
+        let rem = if den < 0 && rem != 0 { rem + den } else { rem };
+        Self::from_i128(rem, rem_den).expect("remainder overflows Rational")
     }
 }
 
-impl std::fmt::Display for Rational {
+/// Writes the numerator and denominator as they are stored, e.g. `7/2`,
+/// `-1/3` or `4/1`.
+impl<T: Integer> Debug for Ratio<T> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{:?}", self)
+        write!(f, "{}/{}", self.0, T::get(self.1))
     }
 }
 
-fn primes() -> impl Iterator<Item = u64> {
-    (0..).map(|e| {
-        let mut lock = PRIMES.lock().unwrap();
-        if lock.is_empty() {
-            lock.push(2)
+/// Writes integers as `n`, proper fractions as `a/b` and everything else as a
+/// mixed number like `3+1/2` or `-3-1/2`. For the default i64 backend the
+/// output always parses back to the same value with [`FromStr`].
+///
+/// Width, fill, alignment, `+` and `0` apply to the whole string, so
+/// `{:>8}` gives `   3+1/2` and `{:+}` gives `+3+1/2`. A precision writes
+/// the value as a decimal with that many digits instead, rounded half to
+/// even, e.g. `{:.3}` gives `3.500`.
+impl<T: Integer> std::fmt::Display for Ratio<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Some(digits) = f.precision() else {
+            // -(3+1/2) as `-3-1/2` rather than `-3+-1/2`
+            let sep = if self.is_negative() { '-' } else { '+' };
+            return display::write_mixed(self, sep, f);
+        };
+        let (negative, num, den) = self.sign_magnitude();
+        let s = positional_string(negative, num, den, 10, digits, RoundingMode::HalfEven);
+        match s.strip_prefix('-') {
+            Some(magnitude) => f.pad_integral(false, "", magnitude),
+            None => f.pad_integral(true, "", &s),
         }
-        lock.get(e).copied().unwrap_or_else(|| {
-            let prime = (lock.last().unwrap() + 1..)
-                .find(|cand| {
-                    for i in lock.iter() {
-                        if (i * i) > *cand {
-                            return true;
-                        }
+    }
+}
 
-                        if (cand % i) == 0 {
-                            return false;
-                        }
-                    }
-                    true
-                })
-                .unwrap();
-            lock.push(prime);
-            prime
-        })
-    })
+// the mantissa and exponent of a finite `x`, whose magnitude is mant * 2^exp
+fn float_parts(x: f64) -> (u64, i32) {
+    let bits = x.to_bits();
+    let biased = ((bits >> 52) & 0x7ff) as i32;
+    let frac = bits & ((1 << 52) - 1);
+    // subnormals lack the implicit leading bit
+    if biased == 0 {
+        (frac, -1074)
+    } else {
+        (frac | 1 << 52, biased - 1075)
+    }
 }
 
-fn gcd(a: u64, b: u64) -> u64 {
-    let mut lowest = a.min(b);
-    let mut highest = a.max(b);
-    let mut gcd = 1;
-    for i in primes() {
-        if lowest / i < 1 {
-            break;
+// `v` written in `radix`
+fn radix_digits(mut v: u128, radix: u32) -> String {
+    let mut out = Vec::new();
+    loop {
+        out.push(char::from_digit((v % radix as u128) as u32, radix).unwrap());
+        v /= radix as u128;
+        if v == 0 {
+            break out.into_iter().rev().collect();
         }
+    }
+}
+
+const fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+// what `Rational::eval` records about the subexpressions it computed
+#[derive(Default)]
+struct Trace<N> {
+    diag: Diagnostics,
+    values: Vec<(Range<usize>, N)>,
+}
 
-        while lowest % i == 0 && highest % i == 0 {
-            highest /= i;
-            lowest /= i;
-            gcd *= i;
+impl<N: Number> Trace<N> {
+    fn record(&mut self, (v, span): &(N, Range<usize>)) {
+        // values too wide for a Rational are past any overflow warning
+        if let Ok(v) = v.to_rational() {
+            self.diag.record(v);
         }
+        self.values.push((span.clone(), v.clone()));
+    }
+}
+
+// running sum in i128, reduced after every step so intermediate
+// denominators only grow as far as the least common multiple
+struct Accumulator {
+    num: i128,
+    den: i128,
+}
+
+impl Default for Accumulator {
+    fn default() -> Self {
+        Self { num: 0, den: 1 }
+    }
+}
+
+impl Accumulator {
+    fn add(&mut self, v: Rational) -> Option<()> {
+        self.add_parts(v.0 as i128, v.1.get() as i128)
+    }
+
+    // adds `v_num/v_den` for a positive `v_den`, such as an exact product
+    fn add_parts(&mut self, v_num: i128, v_den: i128) -> Option<()> {
+        let g = gcd128(v_num.unsigned_abs(), v_den as u128) as i128;
+        let (v_num, v_den) = (v_num / g, v_den / g);
+        let g = gcd128(self.den as u128, v_den as u128) as i128;
+        let num =
+            (self.num.checked_mul(v_den / g)?).checked_add(v_num.checked_mul(self.den / g)?)?;
+        let den = (self.den / g).checked_mul(v_den)?;
+        let g = gcd128(num.unsigned_abs(), den as u128) as i128;
+        (self.num, self.den) = (num / g, den / g);
+        Some(())
+    }
+
+    fn to_rational(&self) -> Option<Rational> {
+        Rational::from_i128(self.num, self.den)
+    }
+}
+
+// the inverse of `n` modulo `m`, in `0..m`, for `n` coprime with `m`
+fn mod_inverse(n: i128, m: i128) -> i128 {
+    let (mut r0, mut r1, mut s0, mut s1) = (m, n.rem_euclid(m), 0i128, 1i128);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 - q * s1);
     }
+    s0.rem_euclid(m)
+}
+
+fn gcd128(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
 
-    gcd
+// full 256-bit product as (high, low) halves, which compare in order
+fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let mid = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let lo = (lo_lo & MASK) | (mid << 64);
+    let hi = a_hi * b_hi + (hi_lo >> 64) + (lo_hi >> 64) + (mid >> 64);
+    (hi, lo)
 }
 
 macro_rules! ops_impl {
@@ -213,45 +2506,190 @@ macro_rules! ops_impl {
                 type Output = Self;
 
                 fn add(self, rhs: $i) -> Self::Output {
-                    Self(self.0 + rhs as i64 * self.1, self.1).normalize()
+                    self + Self::from(rhs)
                 }
             }
             impl AddAssign<$i> for Rational {
-
                 fn add_assign(&mut self, rhs: $i) {
-                    *self = Self(self.0 + rhs as i64 * self.1, self.1).normalize()
+                    *self = *self + rhs
+                }
+            }
+            impl SubAssign<$i> for Rational {
+                fn sub_assign(&mut self, rhs: $i) {
+                    *self = *self - rhs
+                }
+            }
+            impl MulAssign<$i> for Rational {
+                fn mul_assign(&mut self, rhs: $i) {
+                    *self = *self * rhs
+                }
+            }
+            impl DivAssign<$i> for Rational {
+                fn div_assign(&mut self, rhs: $i) {
+                    *self = *self / rhs
                 }
             }
             impl Sub<$i> for Rational {
                 type Output = Self;
 
                 fn sub(self, rhs: $i) -> Self::Output {
-                    Self(self.0 - rhs as i64 * self.1, self.1).normalize()
+                    self - Self::from(rhs)
                 }
             }
             impl Mul<$i> for Rational {
                 type Output = Self;
 
                 fn mul(self, rhs: $i) -> Self::Output {
-                    Self(self.0 * rhs as i64, self.1).normalize()
+                    self * Self::from(rhs)
                 }
             }
-            #[allow(clippy::suspicious_arithmetic_impl)]
             impl Div<$i> for Rational {
                 type Output = Self;
 
                 fn div(self, rhs: $i) -> Self::Output {
-                    Self(self.0, self.1 * rhs as i64).normalize()
+                    self / Self::from(rhs)
+                }
+            }
+            impl Rem<$i> for Rational {
+                type Output = Self;
+
+                fn rem(self, rhs: $i) -> Self::Output {
+                    self % Self::from(rhs)
+                }
+            }
+            impl Add<$i> for &Rational {
+                type Output = Rational;
+
+                fn add(self, rhs: $i) -> Self::Output {
+                    *self + rhs
+                }
+            }
+            impl Sub<$i> for &Rational {
+                type Output = Rational;
+
+                fn sub(self, rhs: $i) -> Self::Output {
+                    *self - rhs
+                }
+            }
+            impl Mul<$i> for &Rational {
+                type Output = Rational;
+
+                fn mul(self, rhs: $i) -> Self::Output {
+                    *self * rhs
+                }
+            }
+            impl Div<$i> for &Rational {
+                type Output = Rational;
+
+                fn div(self, rhs: $i) -> Self::Output {
+                    *self / rhs
+                }
+            }
+        )*
+
+    };
+}
+
+// the operators on references, forwarding to the by-value ones
+macro_rules! ref_ops_impl {
+    [$($tr:ident $f:ident),*] => {
+        $(
+            impl<T: Integer> $tr<&Ratio<T>> for Ratio<T> {
+                type Output = Self;
+
+                fn $f(self, rhs: &Self) -> Self::Output {
+                    self.$f(*rhs)
+                }
+            }
+            impl<T: Integer> $tr<Ratio<T>> for &Ratio<T> {
+                type Output = Ratio<T>;
+
+                fn $f(self, rhs: Ratio<T>) -> Self::Output {
+                    (*self).$f(rhs)
+                }
+            }
+            impl<T: Integer> $tr for &Ratio<T> {
+                type Output = Ratio<T>;
+
+                fn $f(self, rhs: Self) -> Self::Output {
+                    (*self).$f(*rhs)
+                }
+            }
+        )*
+    };
+}
+
+// `a op= b` as `a = a op b`, by value and by reference
+macro_rules! assign_ops_impl {
+    [$($tr:ident $f:ident $op:tt),*] => {
+        $(
+            impl<T: Integer> $tr for Ratio<T> {
+                fn $f(&mut self, rhs: Self) {
+                    *self = *self $op rhs;
+                }
+            }
+            impl<T: Integer> $tr<&Ratio<T>> for Ratio<T> {
+                fn $f(&mut self, rhs: &Self) {
+                    *self = *self $op *rhs;
+                }
+            }
+        )*
+    };
+}
+
+// comparisons with integers, exact for every width
+macro_rules! cmp_impl {
+    [$($i:ident),*] => {
+        $(
+            impl PartialEq<$i> for Rational {
+                fn eq(&self, other: &$i) -> bool {
+                    self.cmp_integer(*other as i128).is_eq()
                 }
             }
+            impl PartialOrd<$i> for Rational {
+                fn partial_cmp(&self, other: &$i) -> Option<Ordering> {
+                    Some(self.cmp_integer(*other as i128))
+                }
+            }
+        )*
+    };
+}
 
+// i64 itself is covered by the From impl of every backend
+macro_rules! from_impl {
+    [$($i:ident),*] => {
+        $(
             impl From<$i> for Rational {
                 fn from(v: $i) -> Self {
-                    Rational(v as i64, 1)
+                    Ratio(v.into(), NZ_ONE)
                 }
             }
         )*
+    };
+}
+
+/// Panics if `v` is above `i64::MAX`, like the operators do on overflow.
+/// The operators with a u64 operand convert it the same way.
+impl From<u64> for Rational {
+    fn from(v: u64) -> Self {
+        Ratio(i64::try_from(v).expect(OVERFLOW), NZ_ONE)
+    }
+}
 
+// conversions to the other integer widths
+macro_rules! try_into_impl {
+    [$($i:ident),*] => {
+        $(
+            /// Fails with [`Error::NotAnInteger`] unless the denominator is 1
+            /// and with [`Error::Overflow`] if the value is out of range.
+            impl TryFrom<Rational> for $i {
+                type Error = Error;
+
+                fn try_from(value: Rational) -> Result<Self> {
+                    $i::try_from(value.to_integer()?).map_err(|_| Error::Overflow { span: None })
+                }
+            }
+        )*
     };
 }
 
@@ -264,16 +2702,31 @@ enum Op {
 }
 
 impl Op {
-    fn compute(&self, a: Rational, b: Rational) -> Result<Rational> {
-        Ok(match self {
-            Op::Star => a * b,
-            Op::Plus => a + b,
-            Op::Min => a - b,
-            Op::Slash => a.checked_div(b)?,
-        })
+    fn compute<N: Number>(&self, a: &N, b: &N) -> Result<N> {
+        match self {
+            Op::Star => a.try_mul(b),
+            Op::Plus => a.try_add(b),
+            Op::Min => a.try_sub(b),
+            Op::Slash => a.try_div(b),
+        }
     }
 }
 
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+type Function = fn(Rational) -> Result<Rational>;
+
+// functions callable from expressions as `name(arg)`
+const FUNCTIONS: &[(&str, Function)] = &[("sqrt", |v| {
+    v.sqrt_exact().ok_or(Error::IrrationalRoot {
+        value: v,
+        degree: 2,
+    })
+})];
+
+// postfix units applying to the preceding number
+const SUFFIXES: &[(&str, u64)] = &[("%", 100), ("‰", 1000), ("bps", 10000)];
+
 const OP_PRECEDENCE: &[&[Op]] = {
     use Op::*;
     &[&[Slash, Star], &[Plus, Min]]
@@ -292,3 +2745,552 @@ impl From<char> for Op {
 }
 
 ops_impl![i32, u32, i64, u64];
+cmp_impl![i32, u32, i64, u64];
+ref_ops_impl![Add add, Sub sub, Mul mul, Div div];
+assign_ops_impl![AddAssign add_assign +, SubAssign sub_assign -, MulAssign mul_assign *, DivAssign div_assign /];
+from_impl![i32, u32];
+try_into_impl![i32, u32, u64];
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn eval(expr: &str) -> Rational {
+        Rational::run_expr(expr).unwrap()
+    }
+
+    fn r(num: i64, den: i64) -> Rational {
+        Rational::new(num, den).unwrap()
+    }
+
+    #[test]
+    fn multi_digit_literals() {
+        assert_eq!(eval("12"), r(12, 1));
+        assert_eq!(eval("12+34"), r(46, 1));
+        assert_eq!(eval("100/7"), r(100, 7));
+        assert_eq!(eval("007"), r(7, 1));
+        assert_eq!(eval("9223372036854775807"), r(i64::MAX, 1));
+        assert_eq!(eval("12%"), r(3, 25));
+        assert_eq!(eval("1.25"), r(5, 4));
+        assert!(matches!(
+            Rational::run_expr("2 3"),
+            Err(Error::InvalidSyntax(2))
+        ));
+    }
+
+    #[test]
+    fn overflowing_literals() {
+        assert!(matches!(
+            Rational::run_expr("9223372036854775808"),
+            Err(Error::Overflow {
+                span: Some(Range { start: 0, end: 19 })
+            })
+        ));
+        assert!(matches!(
+            Rational::run_expr("1+99999999999999999999"),
+            Err(Error::Overflow {
+                span: Some(Range { start: 2, end: 22 })
+            })
+        ));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn big_literals() {
+        let v = BigRational::run_expr("99999999999999999999/3").unwrap();
+        assert_eq!(v.to_radix_fraction_string(10), "33333333333333333333");
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-3"), r(-3, 1));
+        assert_eq!(eval("1--2"), r(3, 1));
+        assert_eq!(eval("2*-3"), r(-6, 1));
+        assert_eq!(eval("--3"), r(3, 1));
+        assert_eq!(eval("-(1+2)/4"), r(-3, 4));
+        assert_eq!(eval("-7/2"), r(-7, 2));
+        assert!(matches!(
+            Rational::run_expr("-*3"),
+            Err(Error::InvalidSyntax(1))
+        ));
+        assert!(matches!(
+            Rational::run_expr("1+*2"),
+            Err(Error::InvalidSyntax(2))
+        ));
+        assert!(matches!(Rational::run_expr("-"), Err(Error::NeedMoreInput)));
+        assert!(matches!(
+            Rational::run_expr("2*-"),
+            Err(Error::NeedMoreInput)
+        ));
+    }
+
+    #[test]
+    fn negatives_are_canonical() {
+        let cases = [
+            (Rational::new(3, -6).unwrap(), (-1, 2)),
+            (Rational::new(-3, -6).unwrap(), (1, 2)),
+            (Rational::reduce_const(4, -6), (-2, 3)),
+            (Rational::from(-4), (-4, 1)),
+            (Rational::from(-4i32), (-4, 1)),
+            ("1/-2".parse().unwrap(), (-1, 2)),
+            ("-0.5".parse().unwrap(), (-1, 2)),
+            ("-3-1/2".parse().unwrap(), (-7, 2)),
+            (eval("1-3/2"), (-1, 2)),
+            (eval("1/(0-2)"), (-1, 2)),
+            (-r(1, 2), (-1, 2)),
+            (r(1, 3) - r(1, 2), (-1, 6)),
+            (r(1, 2) * r(-2, 3), (-1, 3)),
+            (r(1, 2) / r(-3, 4), (-2, 3)),
+            (r(1, 2) / -3, (-1, 6)),
+            (r(-2, 3).recip().unwrap(), (-3, 2)),
+            (r(-1, 2).pow(3), (-1, 8)),
+            (r(-1, 2).pow(-3), (-8, 1)),
+            (r(-1, 2).mediant(r(-1, 3)), (-2, 5)),
+            (Rational::from_f64_exact(-0.5).unwrap(), (-1, 2)),
+            (Rational::approximate(-0.5, 10), (-1, 2)),
+            (
+                Rational::from_continued_fraction(&[-1, 2]).unwrap(),
+                (-1, 2),
+            ),
+        ];
+        for (v, parts) in cases {
+            assert!(v.is_canonical(), "{v:?}");
+            assert_eq!(v.into_parts(), parts);
+        }
+        assert_eq!(Rational32::new(3, -6).unwrap().into_parts(), (-1, 2));
+        assert_eq!((Rational32::from(1) / -2).into_parts(), (-1, 2));
+        assert_eq!(Rational128::new(3, -6).unwrap().into_parts(), (-1, 2));
+    }
+
+    // every pair compares like its indices
+    fn assert_ascending<T: Integer>(values: &[Ratio<T>]) {
+        for (i, a) in values.iter().enumerate() {
+            for (j, b) in values.iter().enumerate() {
+                assert_eq!(a.cmp(b), i.cmp(&j), "{a:?} vs {b:?}");
+                assert_eq!(a == b, i == j, "{a:?} vs {b:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn comparisons_at_the_extremes() {
+        let (min, max) = (i64::MIN, i64::MAX);
+        assert_ascending(&[
+            r(min, 1),
+            r(min + 1, 1),
+            r(min + 1, 2),
+            r(min, 3),
+            r(min, max),
+            r(-1, 1),
+            r(-(max - 1), max),
+            r(-1, max - 1),
+            r(-1, max),
+            r(0, 1),
+            r(1, max),
+            r(1, max - 1),
+            r(max - 1, max),
+            r(1, 1),
+            r(max, max - 1),
+            r(max, 2),
+            r(max - 1, 1),
+            r(max, 1),
+        ]);
+    }
+
+    #[test]
+    fn comparisons_near_the_extremes_of_other_widths() {
+        let r32 = |num, den| Rational32::new(num, den).unwrap();
+        let (min, max) = (i32::MIN, i32::MAX);
+        assert_ascending(&[
+            r32(min, 1),
+            r32(min, max),
+            r32(-(max - 1), max),
+            r32(-1, max),
+            r32(1, max),
+            r32(max - 1, max),
+            r32(max, max - 1),
+            r32(max, 1),
+        ]);
+        let r128 = |num, den| Rational128::new(num, den).unwrap();
+        let (min, max) = (i128::MIN, i128::MAX);
+        assert_ascending(&[
+            r128(min, 1),
+            r128(min + 1, 1),
+            r128(min, max),
+            r128(-(max - 1), max),
+            r128(-(max - 2), max - 1),
+            r128(-1, max),
+            r128(0, 1),
+            r128(1, max),
+            r128(max - 2, max - 1),
+            r128(max - 1, max),
+            r128(max, max - 1),
+            r128(max, 1),
+        ]);
+    }
+
+    #[test]
+    fn integer_comparisons_are_exact() {
+        assert_ne!(Rational::from(-1), u64::MAX);
+        assert!(Rational::from(-1) < u64::MAX);
+        assert!(Rational::from(i64::MAX) < u64::MAX);
+        assert!(r(i64::MAX, 1) > i64::MAX - 1);
+        assert!(r(-1, 2) < 0u32 && r(-1, 2) > -1);
+        assert!(r(i64::MAX, 2) > (i64::MAX / 2) && r(i64::MAX, 2) < (i64::MAX / 2 + 1));
+        assert_eq!(Rational::from(i64::MIN), i64::MIN);
+        assert_eq!(r(6, 2), 3u64);
+        assert_eq!(Rational::from(u32::MAX), u32::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "Rational overflow")]
+    fn from_u64_out_of_range() {
+        let _ = Rational::from(u64::MAX);
+    }
+
+    #[test]
+    fn over_u64() {
+        assert_eq!(u64::MAX.over(u64::MAX), r(1, 1));
+        assert_eq!((i64::MAX as u64).over(2), r(i64::MAX, 2));
+        assert_eq!(3u64.per_cent(), r(3, 100));
+    }
+
+    // any canonical value, with the extremes of each part mixed in
+    fn any_rational() -> impl Strategy<Value = Rational> {
+        let part = prop_oneof![
+            any::<i64>(),
+            -1000..1000i64,
+            Just(i64::MIN),
+            Just(i64::MAX),
+            Just(i64::MIN + 1),
+        ];
+        (part.clone(), part).prop_filter_map("zero or overflowing denominator", |(n, d)| {
+            Rational::new(n, d).ok()
+        })
+    }
+
+    #[test]
+    fn extremes_parse_back() {
+        for v in [i64::MIN, i64::MIN + 1, i64::MAX] {
+            let v = Rational::from(v);
+            assert_eq!(v.to_string().parse::<Rational>().unwrap(), v);
+            assert_eq!(v.to_odds_string().parse::<Rational>().unwrap(), v);
+        }
+        let v = r(i64::MIN + 1, i64::MAX);
+        assert_eq!(v.to_string().parse::<Rational>().unwrap(), v);
+    }
+
+    #[test]
+    fn log_exact_base_one() {
+        assert_eq!(Rational::ONE.log_exact(Rational::ONE), Some(0));
+        assert_eq!(Rational::from(-1).log_exact(Rational::ONE), None);
+        assert_eq!(Rational::from(-1).log_exact(Rational::from(-1)), Some(1));
+        assert_eq!(Rational::ONE.log_exact(Rational::from(-1)), Some(0));
+    }
+
+    #[test]
+    fn scaled_strings_at_the_extremes() {
+        let v = Rational::from(i64::MAX);
+        assert_eq!(v.to_percent_string(), "922337203685477580700%");
+        assert_eq!(
+            r(i64::MIN, 3).to_basis_points_string(),
+            "-30744573456182586026666-2/3bps"
+        );
+        assert_eq!(v.to_percent_string().parse::<Rational>().unwrap(), v);
+    }
+
+    #[test]
+    fn kpi_helpers_at_the_extremes() {
+        let (min, max) = (Rational::from(i64::MIN), Rational::from(i64::MAX));
+        assert!(matches!(
+            percent_change(r(-1, 2), max),
+            Err(Error::Overflow { .. })
+        ));
+        assert!(matches!(
+            percent_change(min, r(1, 1)),
+            Err(Error::Overflow { .. })
+        ));
+        assert_eq!(percent_change(r(-2, 1), r(-1, 1)).unwrap(), r(50, 1));
+        assert!(matches!(
+            relative_diff(max, min),
+            Err(Error::Overflow { .. })
+        ));
+        assert_eq!(
+            relative_diff(max, r(1, 1)).unwrap(),
+            r(max.numer() - 1, max.numer())
+        );
+        assert_eq!(relative_diff(r(0, 1), r(0, 1)).unwrap(), r(0, 1));
+    }
+
+    #[test]
+    fn trailing_zeros() {
+        let long = format!("1.{}", "0".repeat(40));
+        assert_eq!(long.parse::<Rational>().unwrap(), r(1, 1));
+        assert_eq!(eval(&long), r(1, 1));
+        let long = format!("-2.5{}", "0".repeat(40));
+        assert_eq!(long.parse::<Rational>().unwrap(), r(-5, 2));
+        assert_eq!(eval(&format!("{long}*2")), r(-5, 1));
+    }
+
+    #[test]
+    fn durations() {
+        let frame = r(1001, 30000);
+        assert_eq!(
+            frame.to_duration().unwrap(),
+            Duration::from_nanos(33_366_667)
+        );
+        assert!(matches!(frame.try_to_duration(), Err(Error::Inexact(_))));
+        assert_eq!(r(1, 2_000_000_000).to_duration().unwrap(), Duration::ZERO);
+        assert_eq!(
+            r(3, 2_000_000_000).to_duration().unwrap(),
+            Duration::from_nanos(2)
+        );
+        assert!(matches!(r(-1, 2).to_duration(), Err(Error::Inexact(_))));
+        let d = Duration::new(5, 250_000_000);
+        assert_eq!(Rational::from_duration(d), r(21, 4));
+        assert_eq!(Rational::from_duration(d).to_duration().unwrap(), d);
+        assert_eq!(
+            Rational::from(i64::MAX).to_duration().unwrap().as_secs(),
+            i64::MAX as u64
+        );
+    }
+
+    #[test]
+    fn scaling_durations() {
+        let d = r(30001, 1001) * Duration::new(8_640_000, 1);
+        assert_eq!(d, Duration::new(258_949_690, 309_690_340));
+        let d = r(1, 3) * Duration::new(10_000_000_000, 1);
+        assert_eq!(d, Duration::new(3_333_333_333, 333_333_334));
+        // 5/2 and 7/2 ns round to even
+        assert_eq!(r(5, 2) * Duration::from_nanos(1), Duration::from_nanos(2));
+        assert_eq!(r(7, 2) * Duration::from_nanos(1), Duration::from_nanos(4));
+        assert_eq!(r(1, 2) * Duration::from_nanos(5), Duration::from_nanos(2));
+        assert_eq!(r(0, 1) * Duration::MAX, Duration::ZERO);
+    }
+
+    #[test]
+    fn fixed_ratios() {
+        type Bps = FixedRatio<10000>;
+        let v = Bps::try_from(r(1, 4)).unwrap();
+        assert_eq!(v.units(), 2500);
+        assert_eq!(Rational::from(v), r(1, 4));
+        assert_eq!(
+            Rational::from(v + v * 3 - Bps::from_units(1)),
+            r(9999, 10000)
+        );
+        assert!(matches!(Bps::try_from(r(1, 3)), Err(Error::Inexact(_))));
+        let tiny = Bps::from_units(1);
+        assert!(matches!(tiny.checked_mul(tiny), Err(Error::Inexact(v)) if v == r(1, 100_000_000)));
+        assert!(matches!(
+            tiny.checked_div(Bps::from_units(3)),
+            Err(Error::Inexact(_))
+        ));
+        assert_eq!(v.checked_div(v * 2).unwrap().units(), 5000);
+        assert!(matches!(
+            tiny.checked_div(Bps::default()),
+            Err(Error::DivisionByZero)
+        ));
+        let max = Bps::from_units(i64::MAX);
+        assert!(matches!(max.checked_add(tiny), Err(Error::Overflow { .. })));
+        assert!(matches!(
+            Bps::from_units(i64::MIN).checked_sub(tiny),
+            Err(Error::Overflow { .. })
+        ));
+        assert!(matches!(max.checked_mul(max), Err(Error::Overflow { .. })));
+    }
+
+    #[test]
+    #[should_panic(expected = "Rational overflow")]
+    fn fixed_ratio_add_overflows() {
+        let _ = FixedRatio::<10>::from_units(i64::MAX) + FixedRatio::from_units(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Rational overflow")]
+    fn fixed_ratio_neg_overflows() {
+        let _ = -FixedRatio::<10>::from_units(i64::MIN);
+    }
+
+    #[test]
+    fn round_to_denominator_at_the_extremes() {
+        let max = Rational::from(i64::MAX);
+        assert_eq!(max.round_to_denominator(2, RoundingMode::HalfUp), max);
+        let min = Rational::from(i64::MIN);
+        assert_eq!(min.round_to_denominator(u64::MAX, RoundingMode::Floor), min);
+        let v = r(i64::MAX, 3).round_to_denominator(1, RoundingMode::HalfEven);
+        assert_eq!(v, r(i64::MAX / 3, 1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Rational overflow")]
+    fn round_to_denominator_overflows() {
+        r(1, 2).round_to_denominator(u64::MAX, RoundingMode::HalfUp);
+    }
+
+    #[test]
+    fn predicates() {
+        let unreduced: Rational = Ratio(2, NonZeroI64::new(4).unwrap());
+        assert!(r(-3, 4).is_reduced() && r(-3, 4).is_canonical());
+        assert!(!unreduced.is_reduced() && !unreduced.is_canonical());
+        assert!(r(4, 1).is_integer() && !r(4, 3).is_integer());
+        assert!(r(-3, 4).is_proper() && !r(4, 3).is_proper() && !r(1, 1).is_proper());
+    }
+
+    #[test]
+    fn weighted_mean_of_wide_products() {
+        let max = Rational::from(i64::MAX);
+        let two = r(2, 1);
+        assert_eq!(
+            stats::weighted_mean(&[(max, two), (max, two)]).unwrap(),
+            max
+        );
+        let v = stats::weighted_mean(&[(max, two), (-max, r(1, 1))]).unwrap();
+        assert_eq!(v, r(i64::MAX, 3));
+        let zero = stats::weighted_mean(&[(max, r(1, 1)), (max, r(-1, 1))]);
+        assert!(matches!(zero, Err(Error::DivisionByZero)));
+    }
+
+    #[test]
+    fn simplify_steps_with_large_factors() {
+        let steps = simplify_steps(84, -126).unwrap();
+        let factors: Vec<_> = steps.iter().map(|s| s.factor).collect();
+        assert_eq!(factors, [2, 3, 7]);
+        assert_eq!((steps[2].num, steps[2].den), (2, -3));
+        // the largest prime below 2^63
+        let p = 9_223_372_036_854_775_783;
+        let steps = simplify_steps(-p, p).unwrap();
+        assert_eq!(
+            steps,
+            [SimplifyStep {
+                factor: p as u64,
+                num: -1,
+                den: 1
+            }]
+        );
+        // two primes just above the trial division limit stay together
+        let g = 1_048_583 * 1_048_589;
+        let steps = simplify_steps(g * 5, g * 3).unwrap();
+        assert_eq!(
+            steps,
+            [SimplifyStep {
+                factor: g as u64,
+                num: 5,
+                den: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn mixed_parses_back() {
+        assert_eq!("3 1/2".parse::<Rational>().unwrap(), r(7, 2));
+        assert_eq!("-3 1/2".parse::<Rational>().unwrap(), r(-7, 2));
+        assert!(matches!(
+            "3 -1/2".parse::<Rational>(),
+            Err(Error::InvalidSyntax(2))
+        ));
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn big_debug_is_raw() {
+        let v = BigRational::from(Rational::from(-7) / 2);
+        assert_eq!(format!("{v:?}"), "-7/2");
+        assert_eq!(v.to_string(), "-3-1/2");
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn evaluator_matches_expr_gen() {
+        let gens = [
+            testing::ExprGen::new(1),
+            testing::ExprGen::new(2).max_depth(6).operators(&['+', '-']),
+            testing::ExprGen::new(3).literals(0..=i64::MAX as u64),
+            testing::ExprGen::new(4)
+                .operators(&['*', '/'])
+                .literals(1..=1000),
+        ];
+        for mut generator in gens {
+            for _ in 0..500 {
+                let (expr, expected) = generator.generate();
+                match expected {
+                    Ok(v) => assert_eq!(Rational::run_expr(&expr).unwrap(), v, "{expr}"),
+                    Err(_) => assert!(Rational::run_expr(&expr).is_err(), "{expr}"),
+                }
+            }
+        }
+    }
+
+    #[cfg(all(feature = "testing", feature = "bigint"))]
+    #[test]
+    fn big_evaluator_matches_expr_gen() {
+        let mut generator = testing::ExprGen::new(5).literals(0..=i64::MAX as u64);
+        for _ in 0..500 {
+            let (expr, expected) = generator.generate();
+            if let Ok(v) = expected {
+                let big = BigRational::run_expr(&expr).unwrap();
+                assert_eq!(big, BigRational::from(v), "{expr}");
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn operations_stay_canonical(a in any_rational(), b in any_rational()) {
+            prop_assert!(a.is_canonical());
+            let results = [
+                a.checked_add(b),
+                a.checked_sub(b),
+                a.checked_mul(b),
+                a.checked_div(b),
+                a.recip(),
+                a.checked_pow(3),
+                a.checked_pow(-2),
+                Rational::new(b.denom(), a.numer()),
+                Ok(a.saturating_add(b)),
+                Ok(a.saturating_mul(b)),
+                a.checked_mul(a.signum()),
+                Ok(a.fract()),
+                Ok(a.signum()),
+            ];
+            for v in results.into_iter().flatten() {
+                prop_assert!(v.is_canonical(), "{:?}", v);
+            }
+            // the i64 parts truncated, so that they are often too large
+            if let Ok(v) = Rational32::new(a.numer() as i32, b.numer() as i32) {
+                prop_assert!(v.is_canonical(), "{:?}", v);
+            }
+            let v = Rational128::from(a).checked_mul(b.into()).unwrap();
+            prop_assert!(v.is_canonical(), "{:?}", v);
+        }
+
+        #[test]
+        fn comparison_matches_the_difference(a in any_rational(), b in any_rational()) {
+            // the difference of i64 parts always fits in i128
+            let diff = Rational128::from(a).checked_sub(b.into()).unwrap();
+            prop_assert_eq!(a.cmp(&b), diff.numer().cmp(&0));
+            prop_assert_eq!(a == b, diff.numer() == 0);
+        }
+
+        #[test]
+        fn display_parses_back(v in any_rational()) {
+            let styles = [
+                v.to_string(),
+                format!("{v:?}"),
+                v.display_improper().to_string(),
+                v.display_mixed().to_string(),
+                v.to_radix_fraction_string(10),
+                v.to_odds_string(),
+                v.to_percent_string(),
+                v.to_basis_points_string(),
+            ];
+            for s in styles {
+                prop_assert_eq!(s.parse::<Rational>().unwrap(), v, "{}", s);
+            }
+            if let Some(d) = v.to_repeating_decimal(500) {
+                prop_assert_eq!(d.to_string().parse::<Rational>().unwrap(), v);
+            }
+        }
+    }
+}
@@ -1,11 +1,702 @@
-use ratio_calc::Rational;
+#[cfg(feature = "i18n")]
+use ratio_calc::i18n::Lang;
+use std::collections::BTreeMap;
+use std::io::Write;
+
+#[cfg(feature = "bigint")]
+use ratio_calc::BigRational;
+use ratio_calc::{Context, Error, Rational, RoundingMode, simplify_steps};
+
+/// Without translations every message is in English.
+#[cfg(not(feature = "i18n"))]
+#[derive(Clone, Copy, Default)]
+enum Lang {
+    #[default]
+    En,
+}
+
+#[cfg(not(feature = "i18n"))]
+impl Lang {
+    fn from_locale(locale: &str) -> Option<Self> {
+        locale.starts_with("en").then_some(Lang::En)
+    }
+}
+
+const HELP_EN: &str = "\
+usage: ratio-calc [--env] [--plugin LIBRARY] [--lang LANG] [--warn-overflow FACTOR]
+                  [--bigint] [COMMAND]
+
+Without a command, evaluates expressions read from stdin, with
+arbitrary precision after --bigint.
+
+commands:
+  simplify [--steps] NUM/DEN       reduce a fraction
+  convert [--odds] VALUE [--to FORMAT]
+                                   fraction, mixed, percent, permille, bps,
+                                   decimal, decimal:DIGITS, odds or decimal-odds;
+                                   --odds reads A:B as the probability A/(A+B)
+  table --var NAME=FROM..TO EXPR   evaluate EXPR for every NAME in the range
+  cells                            read `NAME = EXPR` cell definitions from stdin
+";
+
+#[cfg(feature = "i18n")]
+const HELP_NL: &str = "\
+gebruik: ratio-calc [--env] [--plugin BIBLIOTHEEK] [--lang TAAL] [--warn-overflow FACTOR]
+                    [--bigint] [COMMANDO]
+
+Zonder commando worden expressies van stdin gelezen en uitgerekend, na
+--bigint met willekeurige precisie.
+
+commando's:
+  simplify [--steps] TELLER/NOEMER  een breuk vereenvoudigen
+  convert [--odds] WAARDE [--to VORM]
+                                    fraction, mixed, percent, permille, bps,
+                                    decimal, decimal:CIJFERS, odds of decimal-odds;
+                                    --odds leest A:B als de kans A/(A+B)
+  table --var NAAM=VAN..TOT EXPR    EXPR uitrekenen voor elke NAAM in het bereik
+  cells                             cellen `NAAM = EXPR` van stdin lezen
+";
+
+/// Display settings changed with `:mode key=value`.
+struct Mode {
+    radix: u32,
+}
+
+impl Mode {
+    fn set(&mut self, args: &str) {
+        for setting in args.split_whitespace() {
+            match setting.split_once('=') {
+                Some(("radix", v)) => match v.parse() {
+                    Ok(radix @ 2..=36) => self.radix = radix,
+                    _ => eprintln!("radix must be in 2..=36"),
+                },
+                _ => eprintln!("unknown mode `{setting}`"),
+            }
+        }
+    }
+
+    fn format(&self, v: &Value) -> String {
+        match v {
+            Value::Rational(v) => v.to_radix_fraction_string(self.radix),
+            #[cfg(feature = "bigint")]
+            Value::Big(v) => v.to_radix_fraction_string(self.radix),
+        }
+    }
+}
+
+/// The result of an expression read from stdin.
+enum Value {
+    Rational(Rational),
+    #[cfg(feature = "bigint")]
+    Big(BigRational),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Rational(v) => v.fmt(f),
+            #[cfg(feature = "bigint")]
+            Value::Big(v) => v.fmt(f),
+        }
+    }
+}
+
+/// Evaluation settings given before the subcommand.
+struct Options {
+    /// `--env` lets expressions refer to environment variables as `$NAME`.
+    env: bool,
+    /// Functions from `--plugin` libraries.
+    ctx: Context,
+    /// From `--lang`, or else the locale.
+    lang: Lang,
+    /// `--warn-overflow FACTOR` warns when an intermediate value came within
+    /// FACTOR of overflowing.
+    warn_overflow: u64,
+    /// `--bigint` evaluates expressions read from stdin with
+    /// `BigRational`, so they never overflow.
+    bigint: bool,
+}
+
+impl Options {
+    fn message(&self, e: &Error) -> String {
+        #[cfg(feature = "i18n")]
+        return e.localized(self.lang);
+        #[cfg(not(feature = "i18n"))]
+        e.to_string()
+    }
+
+    /// Evaluates `expr` in `ctx`, warning on stderr about near overflows.
+    fn eval(&self, expr: &str, ctx: &Context) -> ratio_calc::Result<Rational> {
+        let (res, diag) = Rational::run_expr_with_diagnostics(expr, ctx);
+        if diag.near_overflow(self.warn_overflow) {
+            eprintln!(
+                "warning: intermediate values came within a factor of {} of overflowing",
+                self.warn_overflow
+            );
+        }
+        res
+    }
+
+    /// Evaluates an expression read from stdin like [`eval`](Self::eval), or
+    /// with arbitrary precision after `--bigint`.
+    fn eval_line(&self, expr: &str, ctx: &Context) -> ratio_calc::Result<Value> {
+        #[cfg(feature = "bigint")]
+        if self.bigint {
+            return BigRational::run_expr_with_context(expr, ctx).map(Value::Big);
+        }
+        self.eval(expr, ctx).map(Value::Rational)
+    }
+
+    fn help(&self) -> &'static str {
+        match self.lang {
+            Lang::En => HELP_EN,
+            #[cfg(feature = "i18n")]
+            Lang::Nl => HELP_NL,
+        }
+    }
+
+    /// The context to evaluate `expr` in, binding every `$NAME` it uses to
+    /// the environment variable NAME parsed as a [`Rational`] with `--env`.
+    fn context(&self, expr: &str) -> Result<Context, String> {
+        let mut ctx = self.ctx.clone();
+        if !self.env {
+            return Ok(ctx);
+        }
+        for (i, _) in expr.match_indices('$') {
+            let rest = &expr[i + 1..];
+            let len = rest
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len());
+            let name = &rest[..len];
+            if name.is_empty() {
+                continue;
+            }
+            let value = std::env::var(name).map_err(|e| format!("${name}: {e}"))?;
+            let value = value
+                .parse()
+                .map_err(|e| format!("${name}: invalid value `{value}`: {e}"))?;
+            ctx.set(name, value);
+        }
+        Ok(ctx)
+    }
+}
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Err(e) = run(&args) {
+        eprintln!("{e}");
+        std::process::exit(1);
+    }
+}
+
+fn run(mut args: &[String]) -> Result<(), String> {
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok().filter(|v| !v.is_empty()));
+    let mut opts = Options {
+        env: false,
+        ctx: Context::new(),
+        lang: locale
+            .and_then(|locale| Lang::from_locale(&locale))
+            .unwrap_or_default(),
+        warn_overflow: 0,
+        bigint: false,
+    };
+    loop {
+        match args.first().map(String::as_str) {
+            Some("--env") => opts.env = true,
+            Some("--bigint") if cfg!(feature = "bigint") => opts.bigint = true,
+            Some("--bigint") => {
+                return Err(
+                    "--bigint is not supported, build with the `bigint` feature".to_string()
+                );
+            }
+            Some("--warn-overflow") => {
+                let factor = args.get(1).ok_or("usage: --warn-overflow FACTOR")?;
+                opts.warn_overflow = factor
+                    .parse()
+                    .map_err(|e| format!("invalid factor `{factor}`: {e}"))?;
+                args = &args[1..];
+            }
+            Some("--lang") => {
+                let lang = args.get(1).ok_or("usage: --lang LANG")?;
+                opts.lang = Lang::from_locale(lang)
+                    .ok_or_else(|| format!("unsupported language `{lang}`"))?;
+                args = &args[1..];
+            }
+            Some("--plugin") => {
+                let path = args.get(1).ok_or("usage: --plugin LIBRARY")?;
+                load_plugin(&mut opts.ctx, path)?;
+                args = &args[1..];
+            }
+            _ => break,
+        }
+        args = &args[1..];
+    }
+    match args.first().map(String::as_str) {
+        None => {
+            repl(&opts);
+            Ok(())
+        }
+        Some("--help" | "help") => {
+            print!("{}", opts.help());
+            Ok(())
+        }
+        Some("simplify") => simplify(&args[1..], &opts),
+        Some("convert") => convert(&args[1..], &opts),
+        Some("table") => table(&args[1..], &opts),
+        Some("cells") => {
+            cells(&opts);
+            Ok(())
+        }
+        Some(cmd) => Err(format!("unknown command `{cmd}`")),
+    }
+}
+
+/// Registers the functions of the shared library at `path`, see
+/// [`ratio_calc::plugin`].
+#[cfg(feature = "plugins")]
+fn load_plugin(ctx: &mut Context, path: &str) -> Result<(), String> {
+    use ratio_calc::plugin::{PLUGIN_ENTRY, PluginEntry, register_plugin};
+
+    // SAFETY: loading a plugin trusts it to follow the plugin ABI
+    unsafe {
+        let lib = libloading::Library::new(path).map_err(|e| e.to_string())?;
+        let entry: PluginEntry = *lib
+            .get(PLUGIN_ENTRY.as_bytes())
+            .map_err(|e| format!("{path}: {e}"))?;
+        register_plugin(ctx, entry);
+        // its functions are used until the process exits
+        std::mem::forget(lib);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "plugins"))]
+fn load_plugin(_: &mut Context, path: &str) -> Result<(), String> {
+    Err(format!(
+        "{path}: plugins are not supported, build with the `plugins` feature"
+    ))
+}
+
+/// `simplify [--steps] NUM/DEN` prints the reduced fraction, and with
+/// `--steps` each factor cancelled on the way there.
+fn simplify(args: &[String], opts: &Options) -> Result<(), String> {
+    let (mut steps, mut fraction) = (false, None);
+    for arg in args {
+        match arg.as_str() {
+            "--steps" => steps = true,
+            _ if fraction.is_none() => fraction = Some(arg),
+            _ => return Err(format!("unexpected argument `{arg}`")),
+        }
+    }
+    let fraction = fraction.ok_or("usage: simplify [--steps] NUM/DEN")?;
+    let (num, den) = fraction.split_once('/').unwrap_or((fraction, "1"));
+    let parse = |s: &str| {
+        s.trim()
+            .parse::<i64>()
+            .map_err(|e| format!("invalid fraction `{fraction}`: {e}"))
+    };
+    let (num, den) = (parse(num)?, parse(den)?);
+    let cancelled = simplify_steps(num, den).map_err(|e| opts.message(&e))?;
+    let reduced = Rational::new(num, den).map_err(|e| opts.message(&e))?;
+
+    if steps {
+        println!("{num}/{den}");
+        for step in cancelled {
+            println!("= {}/{} (cancel {})", step.num, step.den, step.factor);
+        }
+    }
+    println!("{}", reduced.to_radix_fraction_string(10));
+    Ok(())
+}
+
+/// `convert [--odds] VALUE [--to FORMAT]` rewrites a decimal, fraction or
+/// `A:B` ratio, optionally with a `%`, `‰` or `bps` suffix, as `fraction`
+/// (the default), `mixed`, `percent`, `permille`, `bps`, `decimal`,
+/// `decimal:DIGITS`, or, taking the value as a probability, `odds` or
+/// `decimal-odds`. With `--odds` the value is read as odds in favour and
+/// converted to its probability first.
+fn convert(args: &[String], opts: &Options) -> Result<(), String> {
+    let usage = "usage: convert [--odds] VALUE [--to FORMAT]";
+    let (mut value, mut format, mut odds) = (None, "fraction", false);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => format = args.next().ok_or(usage)?,
+            "--odds" => odds = true,
+            _ if value.is_none() => value = Some(arg),
+            _ => return Err(format!("unexpected argument `{arg}`")),
+        }
+    }
+    let value = value.ok_or(usage)?;
+    let v: Rational = value
+        .parse()
+        .map_err(|e| format!("invalid value `{value}`: {}", opts.message(&e)))?;
+    let v = if odds {
+        v.odds_to_probability()
+            .map_err(|e| format!("invalid odds `{value}`: {}", opts.message(&e)))?
+    } else {
+        v
+    };
+    let out = match format.split_once(':') {
+        None => match format {
+            "fraction" => v.to_radix_fraction_string(10),
+            "mixed" => v.to_string(),
+            "percent" => v.to_percent_string(),
+            "permille" => v.to_permille_string(),
+            "bps" => v.to_basis_points_string(),
+            "odds" => v
+                .probability_to_odds()
+                .map_err(|e| opts.message(&e))?
+                .to_odds_string(),
+            "decimal-odds" => v
+                .probability_to_decimal_odds()
+                .map_err(|e| opts.message(&e))?
+                .to_string(),
+            "decimal" => {
+                // the fewest digits that represent the value exactly
+                let digits = (0..=18)
+                    .find(|&k| {
+                        v.checked_mul(Rational::from(10i64.pow(k)))
+                            .is_ok_and(|v| v.to_integer().is_ok())
+                    })
+                    .ok_or(format!(
+                        "{v} has no short exact decimal, use decimal:DIGITS"
+                    ))?;
+                v.to_decimal_string(digits as usize, RoundingMode::HalfEven)
+            }
+            _ => return Err(format!("unknown format `{format}`")),
+        },
+        Some(("decimal", digits)) => {
+            let digits = digits
+                .parse()
+                .map_err(|e| format!("invalid digits `{digits}`: {e}"))?;
+            v.to_decimal_string(digits, RoundingMode::HalfEven)
+        }
+        Some(_) => return Err(format!("unknown format `{format}`")),
+    };
+    println!("{out}");
+    Ok(())
+}
+
+/// `table --var NAME=FROM..TO [--format csv|markdown] EXPR` evaluates EXPR
+/// with NAME bound to each integer from FROM to TO inclusive, printing one row
+/// per value.
+fn table(args: &[String], opts: &Options) -> Result<(), String> {
+    let usage = "usage: table --var NAME=FROM..TO [--format csv|markdown] EXPR";
+    let (mut var, mut format, mut expr) = (None, "text", None);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--var" => var = Some(args.next().ok_or(usage)?),
+            "--format" => format = args.next().ok_or(usage)?,
+            _ if expr.is_none() => expr = Some(arg),
+            _ => return Err(format!("unexpected argument `{arg}`")),
+        }
+    }
+    let (var, expr) = (var.ok_or(usage)?, expr.ok_or(usage)?);
+    let (name, range) = var.split_once('=').ok_or(usage)?;
+    let (from, to) = range.split_once("..").ok_or(usage)?;
+    let bound = |s: &str| {
+        s.parse::<i64>()
+            .map_err(|e| format!("invalid range `{range}`: {e}"))
+    };
+    let (from, to) = (bound(from)?, bound(to)?);
+
+    let mut ctx = opts.context(expr)?;
+    let rows = (from..=to).map(|n| {
+        ctx.set(name, Rational::from(n));
+        let value = match opts.eval(expr, &ctx) {
+            Ok(v) => v.to_radix_fraction_string(10),
+            Err(e) => opts.message(&e),
+        };
+        (n.to_string(), value)
+    });
+    match format {
+        "text" => {
+            let rows: Vec<_> = rows.collect();
+            let width = rows
+                .iter()
+                .map(|(n, _)| n.len())
+                .fold(name.len(), usize::max);
+            println!("{name:>width$}  {expr}");
+            for (n, value) in rows {
+                println!("{n:>width$}  {value}");
+            }
+        }
+        "csv" => {
+            let quote = |s: &str| format!("\"{}\"", s.replace('"', "\"\""));
+            println!("{},{}", quote(name), quote(expr));
+            for (n, value) in rows {
+                println!("{n},{}", quote(&value));
+            }
+        }
+        "markdown" => {
+            println!("| {name} | {} |\n|---:|---|", expr.replace('|', "\\|"));
+            for (n, value) in rows {
+                println!("| {n} | {value} |");
+            }
+        }
+        _ => return Err(format!("unknown format `{format}`")),
+    }
+    Ok(())
+}
+
+/// `cells` reads `NAME = EXPR` lines from stdin defining cells that can refer
+/// to each other like in a spreadsheet, and prints every cell after each
+/// definition. Redefining a cell recalculates the cells that depend on it.
+fn cells(opts: &Options) {
+    let mut defs = BTreeMap::new();
     for line in std::io::stdin().lines() {
         let Ok(line) = line else { break };
-        let res = Rational::run_expr(&line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((name, expr)) = line.split_once('=') else {
+            eprintln!("expected `NAME = EXPR`");
+            continue;
+        };
+        let name = name.trim();
+        if !name.starts_with(|c: char| c.is_ascii_alphabetic())
+            || !name.chars().all(|c| c.is_ascii_alphanumeric())
+        {
+            eprintln!("invalid cell name `{name}`");
+            continue;
+        }
+        defs.insert(name.to_string(), expr.trim().to_string());
+
+        let mut values = BTreeMap::new();
+        for name in defs.keys() {
+            // stored in `values` along with the cells it depends on
+            let _ = cell_value(name, &defs, opts, &mut values, &mut Vec::new());
+        }
+        let width = defs.keys().map(|name| name.len()).max().unwrap_or(0);
+        for (name, value) in values {
+            match value {
+                Ok(v) => println!("{name:<width$}  {}", v.to_radix_fraction_string(10)),
+                Err(e) => println!("{name:<width$}  {e}"),
+            }
+        }
+        println!();
+    }
+}
+
+// the value of cell `name`, evaluating the cells it refers to first, with
+// `visiting` the cells whose value is being computed to catch cycles
+fn cell_value(
+    name: &str,
+    defs: &BTreeMap<String, String>,
+    opts: &Options,
+    values: &mut BTreeMap<String, Result<Rational, String>>,
+    visiting: &mut Vec<String>,
+) -> Result<Rational, String> {
+    if let Some(value) = values.get(name) {
+        return value.clone();
+    }
+    if visiting.iter().any(|cell| cell == name) {
+        return Err("circular reference".to_string());
+    }
+    let expr = &defs[name];
+    visiting.push(name.to_string());
+    let value = opts.context(expr).and_then(|mut ctx| {
+        // every name that is not called like a function and is a cell
+        let mut rest = expr.as_str();
+        while let Some(start) = rest.find(|c: char| c.is_ascii_alphabetic()) {
+            rest = &rest[start..];
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            let (dep, after) = rest.split_at(len);
+            rest = after;
+            if defs.contains_key(dep) && !after.starts_with('(') {
+                let value = cell_value(dep, defs, opts, values, visiting)
+                    .map_err(|e| format!("{dep}: {e}"))?;
+                ctx.set(dep, value);
+            }
+        }
+        opts.eval(expr, &ctx).map_err(|e| opts.message(&e))
+    });
+    visiting.pop();
+    values.insert(name.to_string(), value.clone());
+    value
+}
+
+/// Reads expressions from stdin and prints their values.
+fn repl(opts: &Options) {
+    #[cfg(unix)]
+    interrupt::catch();
+    let mut mode = Mode { radix: 10 };
+    let mut history = Vec::new();
+    // the lines of an incomplete expression so far
+    let mut pending = String::new();
+    for line in std::io::stdin().lines() {
+        let Ok(mut line) = line else { break };
+        #[cfg(unix)]
+        if interrupt::take() {
+            pending.clear();
+        }
+        if line.contains('!') {
+            match expand_history(&line, &history) {
+                Ok(expanded) => {
+                    println!("{expanded}");
+                    line = expanded;
+                }
+                Err(e) => {
+                    eprintln!("{e}");
+                    continue;
+                }
+            }
+        }
+        if !pending.is_empty() {
+            line = format!("{} {line}", std::mem::take(&mut pending));
+        }
+
+        let command = line.starts_with(":load") || line.starts_with(":mode");
+        let res = if command {
+            None
+        } else {
+            let ctx = match opts.context(&line) {
+                Ok(ctx) => ctx,
+                Err(e) => {
+                    eprintln!("{e}");
+                    continue;
+                }
+            };
+            match opts.eval_line(&line, &ctx) {
+                Err(Error::NeedMoreInput) => {
+                    pending = line;
+                    #[cfg(unix)]
+                    interrupt::set_pending(true);
+                    print!("... ");
+                    let _ = std::io::stdout().flush();
+                    continue;
+                }
+                res => Some(res),
+            }
+        };
+        #[cfg(unix)]
+        interrupt::set_pending(false);
+        if !line.trim().is_empty() {
+            history.push(line.clone());
+        }
+        if let Some(args) = line.strip_prefix(":load") {
+            load(args.trim(), &mode, opts);
+            continue;
+        }
+        if let Some(args) = line.strip_prefix(":mode") {
+            mode.set(args);
+            continue;
+        }
 
         // parse
-        println!("{:?}", res);
+        match res {
+            Some(Ok(v)) if mode.radix != 10 => println!("Ok({})", mode.format(&v)),
+            Some(Ok(v)) => println!("Ok({v})"),
+            Some(Err(e)) => println!("Err({e:?})"),
+            None => (),
+        }
+    }
+    if !pending.is_empty() {
+        println!("{:?}", Err::<Rational, _>(Error::NeedMoreInput));
+    }
+}
+
+/// Ctrl-C aborts an incomplete expression, and exits as usual otherwise.
+#[cfg(unix)]
+mod interrupt {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+    static PENDING: AtomicBool = AtomicBool::new(false);
+
+    const SIGINT: i32 = 2;
+
+    unsafe extern "C" {
+        fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+        fn _exit(status: i32) -> !;
+    }
+
+    extern "C" fn on_interrupt(_: i32) {
+        if !PENDING.load(Ordering::Relaxed) {
+            // SAFETY: _exit is async-signal-safe
+            unsafe { _exit(128 + SIGINT) };
+        }
+        INTERRUPTED.store(true, Ordering::Relaxed);
+    }
+
+    pub fn catch() {
+        // SAFETY: the handler only touches atomics and calls _exit
+        unsafe { signal(SIGINT, on_interrupt) };
+    }
+
+    /// Whether an incomplete expression is waiting for more input.
+    pub fn set_pending(pending: bool) {
+        PENDING.store(pending, Ordering::Relaxed);
+    }
+
+    /// Whether Ctrl-C was pressed since the last call.
+    pub fn take() -> bool {
+        INTERRUPTED.swap(false, Ordering::Relaxed)
+    }
+}
+
+/// Replaces `!!` with the last input and `!N` with history entry `N`,
+/// counting from 1.
+fn expand_history(line: &str, history: &[String]) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = line;
+    while let Some(i) = rest.find('!') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let entry = if let Some(after) = rest.strip_prefix('!') {
+            rest = after;
+            history.last().ok_or("history is empty")?
+        } else {
+            let len = rest
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(rest.len());
+            let n: usize = rest[..len].parse().map_err(|_| "expected `!!` or `!N`")?;
+            rest = &rest[len..];
+            n.checked_sub(1)
+                .and_then(|i| history.get(i))
+                .ok_or(format!("no history entry {n}"))?
+        };
+        out.push_str(entry);
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// `:load [-k] FILE` evaluates every line of FILE, stopping at the first
+/// error unless `-k` (keep going) is given.
+fn load(args: &str, mode: &Mode, opts: &Options) {
+    let (keep_going, path) = match args.strip_prefix("-k ") {
+        Some(path) => (true, path.trim()),
+        None => (false, args),
+    };
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            eprintln!("{path}: {e}");
+            return;
+        }
+    };
+    for (i, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let res = opts
+            .context(line)
+            .and_then(|ctx| opts.eval_line(line, &ctx).map_err(|e| opts.message(&e)));
+        match res {
+            Ok(v) => println!("{}", mode.format(&v)),
+            Err(e) => {
+                eprintln!("{path}:{}: {e}", i + 1);
+                if !keep_going {
+                    break;
+                }
+            }
+        }
     }
 }
@@ -0,0 +1,76 @@
+//! The ABI of function plugins, shared libraries that add named functions to
+//! a [`Context`].
+//!
+//! A plugin exports a [`PluginEntry`] under the symbol [`PLUGIN_ENTRY`]
+//! returning its table of functions:
+//!
+//! ```ignore
+//! #[unsafe(no_mangle)]
+//! pub unsafe extern "C" fn ratio_calc_functions(len: *mut usize) -> *const PluginFunction {
+//!     static FUNCTIONS: [PluginFunction; 1] = [PluginFunction { name: c"double".as_ptr(), call: double }];
+//!     unsafe { *len = FUNCTIONS.len() };
+//!     FUNCTIONS.as_ptr()
+//! }
+//! ```
+
+use std::ffi::{CStr, c_char};
+
+use crate::{Context, Error, Rational};
+
+/// Computes `f(num / den)` into `out_num / out_den` and returns 0, or
+/// returns a nonzero error code. `num / den` is in canonical form.
+pub type PluginFn =
+    unsafe extern "C" fn(num: i64, den: i64, out_num: *mut i64, out_den: *mut i64) -> i32;
+
+/// A named function in a plugin's table.
+#[repr(C)]
+pub struct PluginFunction {
+    /// Nul-terminated name made of ASCII letters.
+    pub name: *const c_char,
+    pub call: PluginFn,
+}
+
+// the table is static data of the plugin
+unsafe impl Sync for PluginFunction {}
+
+/// Returns the plugin's table of functions, writing its length to `len`.
+pub type PluginEntry = unsafe extern "C" fn(len: *mut usize) -> *const PluginFunction;
+
+/// The symbol a plugin exports its [`PluginEntry`] under.
+pub const PLUGIN_ENTRY: &str = "ratio_calc_functions";
+
+/// Registers every function of the plugin with entry point `entry` in `ctx`.
+/// A nonzero return code of a function fails with
+/// [`Error::FunctionFailed`].
+///
+/// # Safety
+///
+/// `entry` and the functions in its table must follow the ABI above, and stay
+/// loaded for as long as `ctx` or any clone of it is used.
+pub unsafe fn register_plugin(ctx: &mut Context, entry: PluginEntry) {
+    let mut len = 0;
+    let table = unsafe { entry(&mut len) };
+    if table.is_null() {
+        return;
+    }
+    for f in unsafe { std::slice::from_raw_parts(table, len) } {
+        let name = unsafe { CStr::from_ptr(f.name) }
+            .to_string_lossy()
+            .into_owned();
+        let call = f.call;
+        ctx.set_function(name.clone(), move |v: Rational| {
+            let (mut num, mut den) = (0, 0);
+            let code = unsafe { call(v.0, v.1.get(), &mut num, &mut den) };
+            if code != 0 {
+                return Err(Error::FunctionFailed {
+                    name: name.clone(),
+                    code,
+                });
+            }
+            if den == 0 {
+                return Err(Error::DivisionByZero);
+            }
+            Rational::from_i128(num as i128, den as i128).ok_or(Error::Overflow { span: None })
+        });
+    }
+}
@@ -0,0 +1,158 @@
+use std::iter::{Product, Sum};
+use std::ops::{Add, AddAssign, Div, Mul, Sub};
+
+use crate::{Error, Rational, Rational32, Result, RoundingMode};
+
+// The i64 API on 32-bit parts. Everything is evaluated on the widened 64-bit
+// value and narrowed back, panicking like the primitive integers do if the
+// result does not fit.
+
+fn narrow(v: Rational) -> Rational32 {
+    Rational32::try_from(v).expect("Rational32 overflow")
+}
+
+impl Rational32 {
+    fn widen(self) -> Rational {
+        self.into()
+    }
+
+    /// Rounds to an integer using `mode`.
+    pub fn round(self, mode: RoundingMode) -> i32 {
+        // rounding never grows the magnitude past the numerator plus one
+        // unit, which for a denominator above 1 is still in range
+        self.widen().round(mode) as i32
+    }
+
+    /// Rounds to the nearest multiple of `1/den` using `mode`.
+    pub fn round_to_denominator(self, den: u32, mode: RoundingMode) -> Rational32 {
+        narrow(self.widen().round_to_denominator(den as u64, mode))
+    }
+
+    /// Formats as a decimal with exactly `digits` fractional digits, rounding
+    /// the last one using `mode`.
+    pub fn to_decimal_string(self, digits: usize, mode: RoundingMode) -> String {
+        self.widen().to_decimal_string(digits, mode)
+    }
+
+    /// Formats the value scaled to percent, e.g. `1/4` as `25%`.
+    pub fn to_percent_string(self) -> String {
+        self.widen().to_percent_string()
+    }
+
+    /// Formats the value scaled to per-mille, e.g. `1/4` as `250‰`.
+    pub fn to_permille_string(self) -> String {
+        self.widen().to_permille_string()
+    }
+
+    /// Formats the value scaled to basis points, e.g. `1/4` as `2500bps`.
+    pub fn to_basis_points_string(self) -> String {
+        self.widen().to_basis_points_string()
+    }
+
+    /// Whether `|self - other| <= tol`, computed exactly.
+    pub fn approx_eq(&self, other: Self, tol: Rational32) -> bool {
+        self.widen().approx_eq(other.widen(), tol.widen())
+    }
+
+    /// The exact arithmetic mean of `iter`, or `None` if it is empty or the
+    /// mean is not representable.
+    pub fn mean<I: IntoIterator<Item = Rational32>>(iter: I) -> Option<Rational32> {
+        Rational::mean(iter.into_iter().map(Rational::from))?
+            .try_into()
+            .ok()
+    }
+
+    /// The value as an integer, failing with [`Error::NotAnInteger`] unless
+    /// the denominator is 1.
+    pub fn to_integer(&self) -> Result<i32> {
+        if self.denom() == 1 {
+            Ok(self.numer())
+        } else {
+            Err(Error::NotAnInteger(self.widen()))
+        }
+    }
+
+    /// The nearest `f64`, see [`Rational::to_f64`].
+    pub fn to_f64(self) -> f64 {
+        self.widen().to_f64()
+    }
+
+    /// The nearest `f32`, see [`Rational::to_f32`].
+    pub fn to_f32(self) -> f32 {
+        self.widen().to_f32()
+    }
+
+    /// See [`Rational::to_json_value`].
+    #[cfg(feature = "serde_json")]
+    pub fn to_json_value(&self) -> serde_json::Value {
+        self.widen().to_json_value()
+    }
+}
+
+impl TryFrom<Rational32> for i32 {
+    type Error = Error;
+
+    fn try_from(value: Rational32) -> Result<Self> {
+        value.to_integer()
+    }
+}
+
+impl Product for Rational32 {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(1.into(), Self::mul)
+    }
+}
+
+impl Sum for Rational32 {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        narrow(iter.map(Rational::from).sum())
+    }
+}
+
+impl<'a> Sum<&'a Rational32> for Rational32 {
+    fn sum<I: Iterator<Item = &'a Rational32>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+macro_rules! int_ops_impl {
+    [$($rhs:ty),*] => {
+        $(
+            impl Add<$rhs> for Rational32 {
+                type Output = Self;
+
+                fn add(self, rhs: $rhs) -> Self::Output {
+                    self + Rational32::from(rhs)
+                }
+            }
+            impl AddAssign<$rhs> for Rational32 {
+                fn add_assign(&mut self, rhs: $rhs) {
+                    *self = *self + rhs;
+                }
+            }
+            impl Sub<$rhs> for Rational32 {
+                type Output = Self;
+
+                fn sub(self, rhs: $rhs) -> Self::Output {
+                    self - Rational32::from(rhs)
+                }
+            }
+            impl Mul<$rhs> for Rational32 {
+                type Output = Self;
+
+                fn mul(self, rhs: $rhs) -> Self::Output {
+                    self * Rational32::from(rhs)
+                }
+            }
+            impl Div<$rhs> for Rational32 {
+                type Output = Self;
+
+                fn div(self, rhs: $rhs) -> Self::Output {
+                    self / Rational32::from(rhs)
+                }
+            }
+        )*
+    };
+}
+
+int_ops_impl![i32];
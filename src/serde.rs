@@ -0,0 +1,76 @@
+//! Serde support, behind the `serde` feature.
+//!
+//! [`Rational`] itself serializes as a `{"num": 3, "den": 4}` struct. The
+//! modules here select other representations with `#[serde(with = ...)]`:
+//!
+//! ```ignore
+//! #[derive(Serialize, Deserialize)]
+//! struct Recipe {
+//!     #[serde(with = "ratio_calc::serde::tuple")]
+//!     flour: Rational,
+//!     #[serde(with = "ratio_calc::serde::string")]
+//!     sugar: Rational,
+//! }
+//! ```
+//!
+//! Every representation reduces what it reads and rejects a zero
+//! denominator.
+
+use ::serde::de::Error as _;
+use ::serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Rational;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "Rational")]
+struct Parts {
+    num: i64,
+    den: i64,
+}
+
+fn from_parts<'de, D: Deserializer<'de>>(num: i64, den: i64) -> Result<Rational, D::Error> {
+    Rational::new(num, den).map_err(D::Error::custom)
+}
+
+impl Serialize for Rational {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let (num, den) = self.into_parts();
+        Parts { num, den }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Rational {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let Parts { num, den } = Parts::deserialize(deserializer)?;
+        from_parts::<D>(num, den)
+    }
+}
+
+/// A `[num, den]` pair.
+pub mod tuple {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &Rational, serializer: S) -> Result<S::Ok, S::Error> {
+        v.into_parts().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rational, D::Error> {
+        let (num, den) = <(i64, i64)>::deserialize(deserializer)?;
+        from_parts::<D>(num, den)
+    }
+}
+
+/// A string like `"3/4"` or `"-2"`. Reading accepts everything
+/// [`FromStr`](std::str::FromStr) does, e.g. `"0.75"` or `"75%"`.
+pub mod string {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(v: &Rational, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&v.to_radix_fraction_string(10))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Rational, D::Error> {
+        let s = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        s.parse().map_err(D::Error::custom)
+    }
+}
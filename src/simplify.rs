@@ -0,0 +1,48 @@
+use crate::{Error, Result, gcd};
+
+const TRIAL_LIMIT: u64 = 1 << 20;
+
+/// One cancellation while reducing a fraction: the `factor` divided out of
+/// both parts, leaving `num / den`. It is prime except for a last factor
+/// whose own prime factors all exceed the trial division limit of `2^20`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SimplifyStep {
+    pub factor: u64,
+    pub num: i64,
+    pub den: i64,
+}
+
+/// The cancellations that reduce `num / den`, one per prime factor of their
+/// greatest common divisor in ascending order, e.g. `84/126` cancels 2, 3
+/// and 7 to reach `2/3`. Whatever remains after trial division up to `2^20`
+/// is cancelled in one step, so that a large gcd takes no longer than that. The sign is left where it was, so a negative
+/// denominator still has to be moved to the numerator.
+pub fn simplify_steps(num: i64, den: i64) -> Result<Vec<SimplifyStep>> {
+    if den == 0 {
+        return Err(Error::DivisionByZero);
+    }
+    let mut g = gcd(num.unsigned_abs(), den.unsigned_abs());
+    let mut steps = Vec::new();
+    let (mut num, mut den) = (num, den);
+    let mut cancel = |factor: u64| {
+        // only the final prime can be left over, and it is below 2^63
+        num /= factor as i64;
+        den /= factor as i64;
+        steps.push(SimplifyStep { factor, num, den });
+    };
+    // 2 and then the odd candidates
+    let candidates = std::iter::once(2).chain((3..=TRIAL_LIMIT).step_by(2));
+    for p in candidates {
+        if p * p > g {
+            break;
+        }
+        while g.is_multiple_of(p) {
+            g /= p;
+            cancel(p);
+        }
+    }
+    if g > 1 {
+        cancel(g);
+    }
+    Ok(steps)
+}
@@ -0,0 +1,90 @@
+//! Exact statistics over slices of values.
+//!
+//! Intermediate sums are kept in i128, so only a result that does not fit
+//! fails with [`Error::Overflow`]. An empty slice fails with
+//! [`Error::NoValues`].
+
+use crate::{Accumulator, Error, Rational, Result};
+
+fn overflow() -> Error {
+    Error::Overflow { span: None }
+}
+
+// the sum of `values` with its denominator scaled by `count`
+fn scaled_sum(values: impl IntoIterator<Item = Rational>, count: usize) -> Result<Rational> {
+    let mut acc = Accumulator::default();
+    for v in values {
+        acc.add(v).ok_or_else(overflow)?;
+    }
+    acc.den = i128::try_from(count)
+        .ok()
+        .and_then(|count| acc.den.checked_mul(count))
+        .ok_or_else(overflow)?;
+    acc.to_rational().ok_or_else(overflow)
+}
+
+/// The exact sum, zero for no values.
+pub fn sum(values: &[Rational]) -> Result<Rational> {
+    scaled_sum(values.iter().copied(), 1)
+}
+
+/// The exact arithmetic mean.
+pub fn mean(values: &[Rational]) -> Result<Rational> {
+    if values.is_empty() {
+        return Err(Error::NoValues);
+    }
+    scaled_sum(values.iter().copied(), values.len())
+}
+
+/// The mean of `(value, weight)` pairs, `sum(value * weight) /
+/// sum(weight)`. Fails with [`Error::DivisionByZero`] if the weights add up
+/// to zero.
+pub fn weighted_mean(values: &[(Rational, Rational)]) -> Result<Rational> {
+    if values.is_empty() {
+        return Err(Error::NoValues);
+    }
+    // the products are formed and summed in i128, so that only the mean
+    // itself has to fit
+    let (mut products, mut total) = (Accumulator::default(), Accumulator::default());
+    for &(v, w) in values {
+        let (num, den) = v.wide_mul(w);
+        products.add_parts(num, den).ok_or_else(overflow)?;
+        total.add(w).ok_or_else(overflow)?;
+    }
+    if total.num == 0 {
+        return Err(Error::DivisionByZero);
+    }
+    let num = products.num.checked_mul(total.den).ok_or_else(overflow)?;
+    let den = products.den.checked_mul(total.num).ok_or_else(overflow)?;
+    Rational::from_i128(num, den).ok_or_else(overflow)
+}
+
+/// The middle value, or the mean of the two middle values for an even
+/// number of them.
+pub fn median(values: &[Rational]) -> Result<Rational> {
+    if values.is_empty() {
+        return Err(Error::NoValues);
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 1 {
+        Ok(sorted[mid])
+    } else {
+        scaled_sum([sorted[mid - 1], sorted[mid]], 2)
+    }
+}
+
+/// The population variance, the mean of the squared differences from the
+/// mean.
+pub fn variance(values: &[Rational]) -> Result<Rational> {
+    let mean = mean(values)?;
+    let squares = values
+        .iter()
+        .map(|&v| {
+            let diff = v.checked_sub(mean)?;
+            diff.checked_mul(diff)
+        })
+        .collect::<Result<Vec<_>>>()?;
+    scaled_sum(squares, values.len())
+}
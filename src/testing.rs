@@ -0,0 +1,116 @@
+//! Helpers for testing code built on the evaluator.
+
+use std::ops::RangeInclusive;
+
+use crate::{Rational, Result};
+
+/// Generates random syntactically valid expressions together with their
+/// expected value, computed from the generated tree rather than by parsing
+/// the text. Operands that are themselves operations are parenthesized, so
+/// the expected value does not depend on operator precedence.
+///
+/// The same seed always gives the same sequence of expressions.
+#[derive(Debug, Clone)]
+pub struct ExprGen {
+    state: u64,
+    max_depth: u32,
+    operators: Vec<char>,
+    literals: RangeInclusive<u64>,
+}
+
+impl ExprGen {
+    /// A generator of expressions up to 4 operations deep using `+ - * /`
+    /// and literals from 0 to 9.
+    pub fn new(seed: u64) -> Self {
+        Self {
+            // xorshift has to start from a nonzero state
+            state: seed | 1,
+            max_depth: 4,
+            operators: vec!['+', '-', '*', '/'],
+            literals: 0..=9,
+        }
+    }
+
+    /// Sets how deeply operations may nest.
+    pub fn max_depth(mut self, depth: u32) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Sets the operators to choose from, out of `+ - * /`.
+    ///
+    /// Panics if `operators` is empty or contains anything else.
+    pub fn operators(mut self, operators: &[char]) -> Self {
+        assert!(!operators.is_empty(), "no operators to choose from");
+        assert!(
+            operators.iter().all(|op| "+-*/".contains(*op)),
+            "unsupported operator"
+        );
+        self.operators = operators.to_vec();
+        self
+    }
+
+    /// Sets the range of the literals.
+    ///
+    /// Panics if `literals` is empty or goes beyond `i64::MAX`.
+    pub fn literals(mut self, literals: RangeInclusive<u64>) -> Self {
+        assert!(!literals.is_empty(), "empty literal range");
+        assert!(*literals.end() <= i64::MAX as u64, "literal overflows i64");
+        self.literals = literals;
+        self
+    }
+
+    /// The next expression and the result evaluating it should give.
+    pub fn generate(&mut self) -> (String, Result<Rational>) {
+        let mut expr = String::new();
+        let value = self.expr(self.max_depth, &mut expr);
+        (expr, value)
+    }
+
+    fn expr(&mut self, depth: u32, out: &mut String) -> Result<Rational> {
+        if depth == 0 || self.next().is_multiple_of(3) {
+            let (start, end) = (*self.literals.start(), *self.literals.end());
+            let v = match (end - start).checked_add(1) {
+                Some(len) => start + self.next() % len,
+                None => self.next(),
+            };
+            out.push_str(&v.to_string());
+            return Ok(Rational::from(v));
+        }
+        let a = self.operand(depth - 1, out);
+        let i = self.next() % self.operators.len() as u64;
+        let op = self.operators[i as usize];
+        if self.next().is_multiple_of(2) {
+            out.push(op);
+        } else {
+            out.push_str(&format!(" {op} "));
+        }
+        let b = self.operand(depth - 1, out);
+        let (a, b) = (a?, b?);
+        match op {
+            '+' => a.checked_add(b),
+            '-' => a.checked_sub(b),
+            '*' => a.checked_mul(b),
+            _ => a.checked_div(b),
+        }
+    }
+
+    // like `expr`, but in parentheses unless it is a literal
+    fn operand(&mut self, depth: u32, out: &mut String) -> Result<Rational> {
+        let start = out.len();
+        let value = self.expr(depth, out);
+        if out[start..].contains(|c: char| !c.is_ascii_digit()) {
+            out.insert(start, '(');
+            out.push(')');
+        }
+        value
+    }
+
+    // xorshift64*
+    fn next(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}